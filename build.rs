@@ -0,0 +1,70 @@
+//! Compiles `src/exception.m`, the `@try` block behind `ffi::catch_exception`, on macOS.
+//! This only needs the C compiler that comes with Xcode's command line tools, which
+//! are already needed for linking. Like the `cc` crate, it uses the compiler and
+//! archiver set in `CC` and `AR` (or their target-specific variants), if any.
+
+use std::{env, path::PathBuf, process::Command};
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/exception.m");
+
+    if env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("macos") {
+        return;
+    }
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let object = out_dir.join("exception.o");
+    let arch = match env::var("CARGO_CFG_TARGET_ARCH").unwrap().as_str() {
+        "aarch64" => "arm64".to_string(),
+        arch => arch.to_string(),
+    };
+    let cc = tool("CC", "cc");
+    let ar = tool("AR", "ar");
+
+    run(Command::new(cc)
+        .args([
+            "-c",
+            "src/exception.m",
+            "-fobjc-exceptions",
+            "-O2",
+            "-arch",
+            &arch,
+        ])
+        .arg("-o")
+        .arg(&object));
+    run(Command::new(ar)
+        .arg("crs")
+        .arg(out_dir.join("libobjective_rust_exception.a"))
+        .arg(&object));
+
+    println!("cargo:rustc-link-search=native={}", out_dir.display());
+    println!("cargo:rustc-link-lib=static=objective_rust_exception");
+}
+
+/// Finds the program to use for `name` (`CC` or `AR`) from the same environment
+/// variables the `cc` crate reads, most specific first, so cross-compiling setups work
+/// the same way: `CC_<target>`, `CC_<target with underscores>`, `TARGET_CC`, then `CC`.
+/// Falls back to `default` if none are set.
+fn tool(name: &str, default: &str) -> String {
+    let target = env::var("TARGET").unwrap();
+    let vars = [
+        format!("{name}_{target}"),
+        format!("{name}_{}", target.replace('-', "_")),
+        format!("TARGET_{name}"),
+        name.to_string(),
+    ];
+    for var in &vars {
+        println!("cargo:rerun-if-env-changed={var}");
+    }
+
+    vars.iter()
+        .find_map(|var| env::var(var).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_else(|| default.to_string())
+}
+
+fn run(command: &mut Command) {
+    let status = command
+        .status()
+        .unwrap_or_else(|err| panic!("failed to run {command:?}: {err}"));
+    assert!(status.success(), "{command:?} failed with {status}");
+}
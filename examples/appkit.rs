@@ -1,19 +1,15 @@
 //! Makes a window on macOS with AppKit. This doesn't process any events.
 
-use {
-    core::ptr::NonNull,
-    objective_rust::{objrs, ObjcBool},
+use objective_rust::{
+    foundation::{NSPoint, NSRect, NSSize},
+    objc_options, objrs,
 };
 
 fn main() {
-    let shared = NonNull::new(NSApplication::shared()).unwrap();
-    let ns_app = unsafe { NSApplication::from_raw(shared) };
-
-    let window = NonNull::new(NSWindow::alloc()).unwrap();
-    let mut window = unsafe { NSWindow::from_raw(window) };
-    let mut style_mask = NSWindowStyleMask::default();
-    style_mask.closable().resizable().titled();
-    window.init(
+    let ns_app = NSApplication::shared();
+    let style_mask =
+        NSWindowStyleMask::Closable | NSWindowStyleMask::Resizable | NSWindowStyleMask::Titled;
+    let mut window = NSWindow::new(
         NSRect {
             origin: NSPoint { x: 0.0, y: 0.0 },
             size: NSSize {
@@ -23,118 +19,77 @@ fn main() {
         },
         style_mask,
         2,
-        false.into(),
-    );
+        false,
+    )
+    .unwrap();
     window.make_key(std::ptr::null_mut());
+    window.display();
+    assert!(window.is_kind_of_class(NSWindow::get_objc_class()));
+    assert!(window.is_visible());
+    // The frame includes the title bar, so only its width matches the content rect.
+    assert_eq!(window.frame().size.width, 600.0);
 
     ns_app.run();
 
     unreachable!()
 }
 
-#[objrs]
+// Links AppKit; without it, AppKit's classes won't get loaded.
+#[objrs(framework = "AppKit")]
 mod ffi {
     use super::*;
+    use objective_rust::ffi::NSUInteger;
 
     extern "objc" {
         type NSApplication;
 
+        #[singleton]
         #[selector = "sharedApplication"]
-        fn shared() -> *mut Self;
+        fn shared() -> Self;
         fn run(&self);
     }
     extern "objc" {
         type NSWindow;
 
-        fn alloc() -> *mut Self;
-
+        // Allocates a window, and initialises it with `initWithContentRect:...`.
+        #[constructor]
         #[selector = "initWithContentRect:styleMask:backing:defer:"]
-        fn init(
-            &mut self,
+        fn new(
             content_rect: NSRect,
             style_mask: NSWindowStyleMask,
-            backing_store: u64,
-            defer: ObjcBool,
-        );
+            backing_store: NSUInteger,
+            defer: bool,
+        ) -> Option<Self>;
 
         #[selector = "makeKeyAndOrderFront:"]
         fn make_key(&mut self, sender: *mut ());
-    }
+        fn display(&self);
 
-    #[repr(C)]
-    pub struct NSPoint {
-        pub x: f64,
-        pub y: f64,
-    }
-    #[repr(C)]
-    pub struct NSSize {
-        pub width: f64,
-        pub height: f64,
-    }
-    #[repr(C)]
-    pub struct NSRect {
-        pub origin: NSPoint,
-        pub size: NSSize,
+        #[selector = "isKindOfClass:"]
+        fn is_kind_of_class(&self, class: objective_rust::ffi::Class) -> bool;
+        #[selector = "isVisible"]
+        fn is_visible(&self) -> bool;
+        // `NSRect` is too big to return in registers on x86_64, so this is called with
+        // `objc_msgSend_stret` there.
+        fn frame(&self) -> NSRect;
     }
 
-    #[derive(Default)]
-    #[repr(transparent)]
-    pub struct NSWindowStyleMask(u64);
-
-    #[allow(dead_code)] // Every non-deprecated style mask is listed here, for completeness' sake.
-    impl NSWindowStyleMask {
-        pub fn borderless(&mut self) -> &mut Self {
-            self.0 = 0;
-            self
-        }
-        pub fn titled(&mut self) -> &mut Self {
-            self.0 |= 1 << 0;
-            self
-        }
-        pub fn closable(&mut self) -> &mut Self {
-            self.0 |= 1 << 1;
-            self
-        }
-        pub fn miniaturizable(&mut self) -> &mut Self {
-            self.0 |= 1 << 2;
-            self
-        }
-        pub fn resizable(&mut self) -> &mut Self {
-            self.0 |= 1 << 3;
-            self
-        }
-        pub fn unified_title_and_toolbar(&mut self) -> &mut Self {
-            self.0 |= 1 << 12;
-            self
-        }
-        pub fn fullscreen(&mut self) -> &mut Self {
-            self.0 |= 1 << 14;
-            self
-        }
-        pub fn full_size_content_view(&mut self) -> &mut Self {
-            self.0 |= 1 << 15;
-            self
-        }
-        pub fn utility(&mut self) -> &mut Self {
-            self.0 |= 1 << 4;
-            self
-        }
-        pub fn doc_modal(&mut self) -> &mut Self {
-            self.0 |= 1 << 6;
-            self
-        }
-        pub fn non_activating_panel(&mut self) -> &mut Self {
-            self.0 |= 1 << 7;
-            self
-        }
-        pub fn hud_window(&mut self) -> &mut Self {
-            self.0 |= 1 << 13;
-            self
+    objc_options! {
+        // Every non-deprecated style mask is listed here, for completeness' sake.
+        pub NSWindowStyleMask: NSUInteger {
+            Borderless = 0,
+            Titled = 1 << 0,
+            Closable = 1 << 1,
+            Miniaturizable = 1 << 2,
+            Resizable = 1 << 3,
+            UnifiedTitleAndToolbar = 1 << 12,
+            FullScreen = 1 << 14,
+            FullSizeContentView = 1 << 15,
+            Utility = 1 << 4,
+            DocModalWindow = 1 << 6,
+            NonactivatingPanel = 1 << 7,
+            HUDWindow = 1 << 13,
         }
     }
-
-    // Without this, Rust won't link to AppKit and AppKit classes won't get loaded.
-    #[link(name = "AppKit", kind = "framework")]
-    extern "C" {}
 }
 use ffi::*;
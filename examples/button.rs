@@ -0,0 +1,92 @@
+//! Opens a window with a button, which calls a Rust closure when it's clicked.
+
+use std::{cell::Cell, rc::Rc};
+
+use objective_rust::{
+    ffi::{ClosureTarget, NSString},
+    foundation::{NSPoint, NSRect, NSSize},
+    objrs,
+};
+
+fn main() {
+    let ns_app = NSApplication::shared();
+    let mut window = NSWindow::new(rect(300.0, 200.0), TITLED | CLOSABLE, 2, false).unwrap();
+    let mut button = NSButton::new(rect(120.0, 32.0)).unwrap();
+    button.set_title(NSString::new("Click me").as_ptr());
+
+    let clicks = Rc::new(Cell::new(0));
+    // The button doesn't retain its target, so this has to outlive it.
+    let target = ClosureTarget::new({
+        let clicks = clicks.clone();
+        move || {
+            clicks.set(clicks.get() + 1);
+            println!("Clicked {} times", clicks.get());
+        }
+    });
+    unsafe { target.attach_to(button.into_raw().as_ptr().cast()) };
+
+    // `performClick:` sends the button's action, like a real click.
+    button.perform_click(std::ptr::null_mut());
+    assert_eq!(clicks.get(), 1);
+
+    window.set_content_view(button.into_raw().as_ptr().cast());
+    window.make_key(std::ptr::null_mut());
+    ns_app.run();
+
+    unreachable!()
+}
+
+fn rect(width: f64, height: f64) -> NSRect {
+    NSRect {
+        origin: NSPoint { x: 0.0, y: 0.0 },
+        size: NSSize { width, height },
+    }
+}
+
+const TITLED: NSUInteger = 1 << 0;
+const CLOSABLE: NSUInteger = 1 << 1;
+
+#[objrs(framework = "AppKit")]
+mod ffi {
+    use super::*;
+    pub use objective_rust::ffi::NSUInteger;
+
+    extern "objc" {
+        type NSApplication;
+
+        #[singleton]
+        #[selector = "sharedApplication"]
+        fn shared() -> Self;
+        fn run(&self);
+    }
+    extern "objc" {
+        type NSWindow;
+
+        #[constructor]
+        #[selector = "initWithContentRect:styleMask:backing:defer:"]
+        fn new(
+            content_rect: NSRect,
+            style_mask: NSUInteger,
+            backing_store: NSUInteger,
+            defer: bool,
+        ) -> Option<Self>;
+
+        #[selector = "makeKeyAndOrderFront:"]
+        fn make_key(&mut self, sender: *mut ());
+        #[selector = "setContentView:"]
+        fn set_content_view(&mut self, view: *mut ());
+    }
+    extern "objc" {
+        type NSButton;
+
+        #[constructor]
+        #[selector = "initWithFrame:"]
+        fn new(frame: NSRect) -> Option<Self>;
+
+        #[selector = "setTitle:"]
+        fn set_title(&mut self, title: *mut ());
+        #[selector = "performClick:"]
+        fn perform_click(&self, sender: *mut ());
+    }
+}
+use ffi::*;
@@ -0,0 +1,60 @@
+//! Builds an `NSArray` from several strings, with a variadic method, and stashes Rust
+//! state in an instance variable of a class defined from Rust, which is then only held
+//! weakly.
+
+use std::ffi::c_void;
+
+use objective_rust::{
+    ffi::{Id, NSStr, NSString, WeakId},
+    objrs,
+};
+
+fn main() {
+    let strings = ["one", "two", "three"].map(NSString::new);
+    let objects = strings.each_ref().map(NSString::as_ptr);
+    let array = unsafe { NSArray::from_raw(NSArray::alloc().unwrap()) };
+    let array = array.init_with_objects(&objects);
+
+    assert_eq!(array.count(), 3);
+    let second = unsafe { NSStr::from_ptr(array.object_at_index(1)) };
+    assert_eq!(second.as_str(), "two");
+
+    let mut holder = unsafe { StateHolder::from_raw(StateHolder::new().unwrap()) };
+    assert!(holder.state().is_null());
+    let state = Box::into_raw(Box::new(42_u32));
+    holder.set_state(state.cast());
+    assert_eq!(holder.state(), state.cast::<c_void>());
+    drop(unsafe { Box::from_raw(holder.state().cast::<u32>()) });
+
+    // Weak references don't keep the object alive.
+    let holder = Id::from(holder);
+    let weak = WeakId::new(&holder);
+    assert!(weak.upgrade().is_some());
+    drop(holder);
+    assert!(weak.upgrade().is_none());
+}
+
+#[objrs]
+extern "objc" {
+    type NSArray;
+
+    #[nullable]
+    fn alloc() -> *mut Self;
+    // Takes a nil-terminated list of objects; objective-rust adds the nil.
+    #[variadic]
+    #[selector = "initWithObjects:"]
+    fn init_with_objects(self, objects: &[*mut ()]) -> Self;
+
+    fn count(&self) -> usize;
+    #[selector = "objectAtIndex:"]
+    fn object_at_index(&self, index: usize) -> *mut ();
+}
+
+#[objrs]
+extern "objc" {
+    #[ivar(state: *mut c_void)]
+    type StateHolder: NSObject;
+
+    #[nullable]
+    fn new() -> *mut Self;
+}
@@ -0,0 +1,78 @@
+//! Observes a window's resize notifications with a Rust closure, and resizes the window
+//! to trigger one.
+
+use std::{cell::Cell, rc::Rc};
+
+use objective_rust::{
+    ffi::NotificationObserver,
+    foundation::{NSPoint, NSRect, NSSize},
+    objrs,
+};
+
+fn main() {
+    let _ns_app = NSApplication::shared();
+    let mut window = NSWindow::new(rect(600.0, 400.0), TITLED | RESIZABLE, 2, false).unwrap();
+
+    let resizes = Rc::new(Cell::new(0));
+    let observer = NotificationObserver::new(
+        "NSWindowDidResizeNotification",
+        window.into_raw().as_ptr().cast(),
+        {
+            let resizes = resizes.clone();
+            move || {
+                resizes.set(resizes.get() + 1);
+                println!("The window was resized");
+            }
+        },
+    );
+
+    // The notification is posted on the main thread, so the closure is called right
+    // away, while the frame is set.
+    window.set_frame(rect(800.0, 500.0), true);
+    assert_eq!(resizes.get(), 1);
+
+    // Dropping the observer unregisters it.
+    drop(observer);
+    window.set_frame(rect(600.0, 400.0), true);
+    assert_eq!(resizes.get(), 1);
+}
+
+fn rect(width: f64, height: f64) -> NSRect {
+    NSRect {
+        origin: NSPoint { x: 0.0, y: 0.0 },
+        size: NSSize { width, height },
+    }
+}
+
+const TITLED: NSUInteger = 1 << 0;
+const RESIZABLE: NSUInteger = 1 << 3;
+
+#[objrs(framework = "AppKit")]
+mod ffi {
+    use super::*;
+    pub use objective_rust::ffi::NSUInteger;
+
+    extern "objc" {
+        type NSApplication;
+
+        #[singleton]
+        #[selector = "sharedApplication"]
+        fn shared() -> Self;
+    }
+    extern "objc" {
+        type NSWindow;
+
+        #[constructor]
+        #[selector = "initWithContentRect:styleMask:backing:defer:"]
+        fn new(
+            content_rect: NSRect,
+            style_mask: NSUInteger,
+            backing_store: NSUInteger,
+            defer: bool,
+        ) -> Option<Self>;
+
+        #[selector = "setFrame:display:"]
+        fn set_frame(&mut self, frame: NSRect, display: bool);
+    }
+}
+use ffi::*;
@@ -0,0 +1,2238 @@
+use {
+    crate::{
+        parser::ParserOutput, Argument, CfReturn, Class, Error, Function, MacroConfig, Mutability,
+        PanicStrategy, Protocol, SelfReference, Static, Type, VTableStorage,
+    },
+    proc_macro::{Delimiter, Group, TokenStream, TokenTree},
+    std::fmt::Display,
+};
+
+/// The most variadic arguments a `#[variadic]` method can be called with.
+const VARIADIC_MAX: usize = 16;
+
+pub fn generate(
+    parser_output: Vec<ParserOutput>,
+    config: &MacroConfig,
+) -> Result<TokenStream, Error> {
+    let mut result = TokenStream::new();
+
+    // Linking a framework is what loads its classes. Nothing is linked on other
+    // platforms, where the `stubs` feature stands in for the runtime.
+    for framework in &config.frameworks {
+        let link = format!(
+            r#"
+            #[cfg(target_os = "macos")]
+            #[link(name = "{framework}", kind = "framework")]
+            extern "C" {{}}
+            "#
+        );
+        result.extend([link.parse::<TokenStream>().unwrap()]);
+    }
+    for ty in &config.enums {
+        let check = format!(
+            "
+            const _: () = {{
+                fn check<T: objective_rust::ObjcEnum>() {{}}
+                let _ = check::<{ty}>;
+            }};
+            "
+        );
+        result.extend([check.parse::<TokenStream>().unwrap()]);
+    }
+
+    for output in parser_output {
+        match output {
+            ParserOutput::Class(class) => {
+                result.extend([class.to_string().parse::<TokenStream>().unwrap()]);
+                result.extend(class.implemented_methods());
+            }
+            ParserOutput::Protocol(protocol) => {
+                result.extend([protocol.to_string().parse::<TokenStream>().unwrap()]);
+            }
+            ParserOutput::Static(item) => {
+                result.extend([item.to_string().parse::<TokenStream>().unwrap()]);
+            }
+            ParserOutput::RawToken(token) => result.extend([token]),
+        }
+    }
+
+    Ok(result)
+}
+
+impl Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            name,
+            cfg,
+            attributes,
+            methods,
+        } = self;
+        let cfg = cfg.concat();
+        let attributes = attributes.concat();
+
+        // Protocols without methods don't need anything implemented, so every class can
+        // adopt them.
+        let trait_name = format!("{name}Methods");
+        let methods_trait = match methods.is_empty() {
+            true => format!(
+                "
+                {cfg}
+                /// The `{name}` protocol doesn't have any required methods.
+                pub trait {trait_name}: objective_rust::ffi::BoundClass {{
+                    #[doc(hidden)]
+                    fn register_methods(
+                        _builder: &mut objective_rust::ffi::ClassBuilder,
+                        _strategy: objective_rust::ffi::PanicStrategy,
+                    ) {{}}
+                }}
+                {cfg}
+                impl<T: objective_rust::ffi::BoundClass> {trait_name} for T {{}}
+                "
+            ),
+            false => {
+                let signatures = methods
+                    .iter()
+                    .map(Function::trait_signature)
+                    .collect::<String>();
+                let registrations = methods
+                    .iter()
+                    .map(|method| method.protocol_registration(&trait_name))
+                    .collect::<String>();
+                format!(
+                    "
+                    {cfg}
+                    /// The `{name}` protocol's required methods. Classes defined from Rust
+                    /// that adopt the protocol, with `#[protocols({name})]`, implement them
+                    /// with this trait, and they're added to the class when it's
+                    /// registered.
+                    pub trait {trait_name}: objective_rust::ffi::BoundClass {{
+                        {signatures}
+
+                        #[doc(hidden)]
+                        fn register_methods(
+                            builder: &mut objective_rust::ffi::ClassBuilder,
+                            strategy: objective_rust::ffi::PanicStrategy,
+                        ) where
+                            Self: Sized,
+                        {{
+                            {registrations}
+                        }}
+                    }}
+                    "
+                )
+            }
+        };
+
+        write!(
+            f,
+            r#"
+            {methods_trait}
+            {cfg}
+            {attributes}
+            #[allow(dead_code)]
+            pub struct {name};
+
+            {cfg}
+            #[allow(dead_code)]
+            impl {name} {{
+                /// Returns the Objective-C protocol this struct binds to. Panics if it
+                /// isn't loaded (see [`objective_rust::ffi::get_protocol`]).
+                pub fn get_objc_protocol() -> objective_rust::ffi::Protocol {{
+                    objective_rust::ffi::get_protocol("{name}")
+                        .expect("the `{name}` protocol isn't loaded")
+                }}
+            }}
+            "#
+        )
+    }
+}
+
+impl Display for Static {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            name,
+            ty,
+            cfg,
+            attributes,
+        } = self;
+        let cfg = cfg.concat();
+        let attributes = attributes.concat();
+
+        // The symbol comes from a linked framework, which only exists on macOS.
+        write!(
+            f,
+            r#"
+            {cfg}
+            {attributes}
+            #[allow(non_snake_case, dead_code)]
+            pub fn {name}() -> {ty} {{
+                #[cfg(target_os = "macos")]
+                extern "C" {{
+                    #[allow(non_upper_case_globals)]
+                    static {name}: {ty};
+                }}
+
+                #[cfg(target_os = "macos")]
+                return unsafe {{ {name} }};
+                #[cfg(not(target_os = "macos"))]
+                panic!("objective-rust only supports macOS")
+            }}
+            "#
+        )
+    }
+}
+
+impl Display for Class {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let class_name = &self.name;
+        let instance_name = self.instance_name();
+        let cfg = self.cfg.concat();
+        let attributes = self.attributes.concat();
+        // Users can't annotate the generated code themselves, so silence the lints it's
+        // expected to trigger: vtable entries and helpers are unused if the methods
+        // calling them are, method names often mirror Objective-C's camelCase, and the
+        // generated code still has to use deprecated classes and methods.
+        let allow = "#[allow(dead_code, non_snake_case, deprecated)]";
+        let on_drop = match &self.on_drop {
+            Some(func) => format!("{func}(self.0);"),
+            None => String::new(),
+        };
+        let vtable_storage = match self.vtable_storage {
+            VTableStorage::ThreadLocal => format!(
+                "
+                thread_local! {{
+                    static VTABLE: {class_name}VTable = {class_name}VTable::load();
+                }}
+                VTABLE.with(f)
+                "
+            ),
+            VTableStorage::Global => format!(
+                "
+                static VTABLE: std::sync::OnceLock<{class_name}VTable> =
+                    std::sync::OnceLock::new();
+                f(VTABLE.get_or_init({class_name}VTable::load))
+                "
+            ),
+        };
+        // Users can derive their own `Debug` instead.
+        let debug = match self
+            .attributes
+            .iter()
+            .any(|attr| attr.starts_with("#[derive") && attr.contains("Debug"))
+        {
+            true => String::new(),
+            false => format!(
+                "
+                {cfg}
+                {allow}
+                impl core::fmt::Debug for {class_name} {{
+                    /// Writes the instance's `description`.
+                    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {{
+                        unsafe {{ objective_rust::ffi::write_description(self.0, f) }}
+                    }}
+                }}
+                {cfg}
+                {allow}
+                impl core::fmt::Debug for {class_name}Ref<'_> {{
+                    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {{
+                        core::fmt::Debug::fmt(&**self, f)
+                    }}
+                }}
+                "
+            ),
+        };
+        let comparable = match self.comparable {
+            true => format!(
+                "
+                {cfg}
+                {allow}
+                impl PartialEq for {class_name} {{
+                    fn eq(&self, other: &Self) -> bool {{
+                        self.cmp(other) == core::cmp::Ordering::Equal
+                    }}
+                }}
+                {cfg}
+                {allow}
+                impl Eq for {class_name} {{}}
+                {cfg}
+                {allow}
+                impl PartialOrd for {class_name} {{
+                    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {{
+                        Some(self.cmp(other))
+                    }}
+                }}
+                {cfg}
+                {allow}
+                impl Ord for {class_name} {{
+                    /// Compares the instances with `compare:`.
+                    fn cmp(&self, other: &Self) -> core::cmp::Ordering {{
+                        unsafe {{ objective_rust::ffi::compare(self.0, other.0) }}
+                    }}
+                }}
+                "
+            ),
+            false => String::new(),
+        };
+        let derive_eq = match self.derive_eq {
+            true => format!(
+                "
+                {cfg}
+                {allow}
+                impl PartialEq for {class_name} {{
+                    /// Compares the instances with `isEqual:`, or by pointer if the class
+                    /// doesn't implement it.
+                    fn eq(&self, other: &Self) -> bool {{
+                        {class_name}VTable::with(|vtable| match vtable.is_equal_selector {{
+                            Some(sel) => {{
+                                let instance = objective_rust::ffi::Instance::from_ptr(self.0);
+                                let is_equal: objective_rust::ObjcBool = unsafe {{
+                                    objective_rust::ffi::msg_send(instance, sel, (other.0.as_ptr(),))
+                                }};
+                                is_equal.into()
+                            }}
+                            None => self.0 == other.0,
+                        }})
+                    }}
+                }}
+                {cfg}
+                {allow}
+                impl Eq for {class_name} {{}}
+                "
+            ),
+            false => String::new(),
+        };
+        let thread_safe = match self.thread_safe {
+            true => format!(
+                "
+                {cfg}
+                /// Safety: the binding is marked `#[thread_safe]`, which asserts that the
+                /// class' instances can be used from any thread.
+                unsafe impl Send for {class_name} {{}}
+                {cfg}
+                unsafe impl Sync for {class_name} {{}}
+                "
+            ),
+            false => String::new(),
+        };
+        let derive_hash = match self.derive_hash {
+            true => format!(
+                "
+                {cfg}
+                {allow}
+                impl core::hash::Hash for {class_name} {{
+                    /// Hashes the instance's `hash`, or its pointer if the class doesn't
+                    /// implement it.
+                    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {{
+                        {class_name}VTable::with(|vtable| match vtable.hash_selector {{
+                            Some(sel) => {{
+                                let instance = objective_rust::ffi::Instance::from_ptr(self.0);
+                                let hash: usize =
+                                    unsafe {{ objective_rust::ffi::msg_send(instance, sel, ()) }};
+                                hash.hash(state)
+                            }}
+                            None => self.0.hash(state),
+                        }})
+                    }}
+                }}
+                "
+            ),
+            false => String::new(),
+        };
+        let mut struct_fns = String::new();
+        if self.description {
+            for (name, selector) in [
+                ("description", "description"),
+                ("debug_description", "debugDescription"),
+            ] {
+                struct_fns += &format!(
+                    r#"
+                    /// Returns the instance's `{selector}` as a Rust [`String`]. Returns an
+                    /// empty string if it was nil.
+                    pub fn {name}(&self) -> String {{
+                        let sel = objective_rust::ffi::get_selector("{selector}").unwrap();
+                        unsafe {{ objective_rust::ffi::get_string(self.0, sel) }}
+                    }}
+                    "#
+                );
+            }
+        }
+        let mut vtable_entries = String::new();
+        let mut vtable_setup = String::new();
+        let mut vtable_constructor = String::new();
+        let mut registrations = String::new();
+        let mut load_checks = String::new();
+        let mut ivar_checks = String::new();
+        for (ivar, ty) in &self.ivars {
+            // `_name` ivars get `name` and `set_name` accessors.
+            let name = ivar.trim_start_matches('_');
+            // The runtime only reads and writes ivars as pointers, so other sizes are
+            // caught at compile time instead of panicking on every access.
+            ivar_checks += &format!(
+                r#"
+                {cfg}
+                const _: () = assert!(
+                    core::mem::size_of::<{ty}>() == core::mem::size_of::<*mut ()>(),
+                    "`{class_name}`'s `{ivar}` ivar has to be pointer-sized"
+                );
+                "#
+            );
+            struct_fns += &format!(
+                r#"
+                /// Returns the `{ivar}` instance variable.
+                pub fn {name}(&self) -> {ty} {{
+                    let instance = objective_rust::ffi::Instance::from_ptr(self.0);
+                    unsafe {{ objective_rust::ffi::object_get_ivar(instance, "{ivar}") }}
+                        .expect("`{class_name}` doesn't have a `{ivar}` ivar")
+                }}
+
+                /// Sets the `{ivar}` instance variable.
+                // The runtime only stores the value; it doesn't dereference pointers.
+                #[allow(clippy::not_unsafe_ptr_arg_deref)]
+                pub fn set_{name}(&mut self, value: {ty}) {{
+                    let instance = objective_rust::ffi::Instance::from_ptr(self.0);
+                    let set = unsafe {{ objective_rust::ffi::object_set_ivar(instance, "{ivar}", value) }};
+                    assert!(set, "`{class_name}` doesn't have a `{ivar}` ivar");
+                }}
+                "#
+            );
+            registrations += &format!(
+                r#"
+                let added = builder.add_ivar::<{ty}>("{ivar}");
+                assert!(added, "`{class_name}` declares the `{ivar}` ivar more than once");
+                "#
+            );
+        }
+        let panic_strategy = match self.panic_strategy {
+            PanicStrategy::Abort => "Abort",
+            PanicStrategy::Throw => "Throw",
+        };
+        for protocol in &self.protocols {
+            registrations += &format!(
+                r#"
+                let protocol = objective_rust::ffi::get_protocol("{protocol}")
+                    .expect("`{class_name}` adopts `{protocol}`, which isn't loaded");
+                builder.add_protocol(protocol);
+                <{class_name} as {protocol}Methods>::register_methods(
+                    &mut builder,
+                    objective_rust::ffi::PanicStrategy::{panic_strategy},
+                );
+                "#
+            );
+        }
+        if self.derive_eq {
+            vtable_entries += "is_equal_selector: Option<objective_rust::ffi::Selector>,";
+            vtable_setup += r#"
+                let is_equal_selector = objective_rust::ffi::get_selector("isEqual:")
+                    .filter(|sel| objective_rust::ffi::class_responds_to(class, *sel));
+            "#;
+            vtable_constructor += "is_equal_selector,";
+        }
+        if self.derive_hash {
+            vtable_entries += "hash_selector: Option<objective_rust::ffi::Selector>,";
+            vtable_setup += r#"
+                let hash_selector = objective_rust::ffi::get_selector("hash")
+                    .filter(|sel| objective_rust::ffi::class_responds_to(class, *sel));
+            "#;
+            vtable_constructor += "hash_selector,";
+        }
+
+        for method in &self.methods {
+            // Methods implemented in Rust are added to the class when it's registered,
+            // and generated separately so their bodies keep their spans.
+            if method.body.is_some() {
+                registrations +=
+                    &method.registration(class_name, &instance_name, self.panic_strategy);
+                continue;
+            }
+
+            let Function {
+                name,
+                return_type,
+                args,
+                self_reference,
+                selector,
+                cfg,
+                cf_return,
+                expose_cmd,
+                handle_return,
+                returns_as,
+                bool_is_success,
+                borrowed_return,
+                nullable,
+                out_struct,
+                throws,
+                error_param,
+                singleton,
+                nil_is_err,
+                variadic,
+                out_params,
+                constructor,
+                needs_pool,
+                cached,
+                body: _,
+                attributes,
+            } = method;
+            let fn_cfg = cfg.concat();
+            let fn_attributes = attributes.concat();
+            // Only `cfg` can be applied to the method's vtable field, since it isn't an item.
+            let vtable_cfg = cfg
+                .iter()
+                .filter(|cfg| cfg.starts_with("#[cfg("))
+                .map(String::as_str)
+                .collect::<String>();
+            let selector = selector.as_ref().unwrap_or(name);
+
+            // With `#[expose_cmd]`, the caller chooses the selector (`_cmd`) that's
+            // passed to the implementation, instead of the one it was loaded with.
+            let mut args_with_types = if *expose_cmd {
+                ", cmd: objective_rust::ffi::Selector".to_string()
+            } else {
+                String::new()
+            };
+            let mut ffi_args_with_types = String::new();
+            let mut args_no_types = String::new();
+            // The error parameter isn't part of the method's signature; it's passed a
+            // local instead.
+            let (args, error_arg) = match (error_param, args.split_last()) {
+                (true, Some((error_arg, args))) => (args, Some(error_arg)),
+                _ => (args.as_slice(), None),
+            };
+            // Variadic arguments are spread into the call, after the other arguments.
+            let (args, variadic_arg) = match (variadic, args.split_last()) {
+                (true, Some((variadic_arg, args))) => (args, Some(variadic_arg)),
+                _ => (args, None),
+            };
+            for arg in args {
+                let Argument {
+                    name,
+                    ty,
+                    consumed,
+                    by_ref,
+                } = arg;
+                let self_ty = ty.self_ty(class_name, &instance_name);
+                args_with_types += &match ty {
+                    Type::Block(ty, _) => format!(", {name}: &{ty}"),
+                    ty if *by_ref => format!(", {name}: &{}", ty.format(Some(self_ty))),
+                    ty => format!(", {name}: {}", ty.format(Some(self_ty))),
+                };
+
+                if *by_ref {
+                    // A bitwise copy of the value has the same ABI as the value, and
+                    // isn't dropped (or released) after it's moved into the call, so
+                    // this borrows the caller's reference for the call.
+                    ffi_args_with_types += &format!(", {name}: {}", ty.format(Some(self_ty)));
+                    args_no_types += &format!(", unsafe {{ core::ptr::read({name}) }}");
+                } else if *consumed {
+                    // The method takes ownership of the object. Bound classes are
+                    // `#[repr(transparent)]`, so the wrapper is passed as the instance
+                    // pointer, and moving it into the call means it's never dropped (and
+                    // never released).
+                    ffi_args_with_types += &format!(", {name}: {}", ty.format(Some(class_name)));
+                    args_no_types += &format!(", {name}");
+                } else if let Type::Slice(ty, _) = ty {
+                    // Slices are passed as a pointer to their first element and their
+                    // length. Bound classes are `#[repr(transparent)]`, so a slice of them
+                    // can be passed as an array of instance pointers.
+                    let ty = ty.format(Some(self_ty));
+                    ffi_args_with_types += &format!(", {name}: *const {ty}, {name}_len: usize");
+                    args_no_types += &format!(", {name}.as_ptr(), {name}.len()");
+                } else if let Type::Block(ty, _) = ty {
+                    // Blocks are passed as a pointer to the block literal, which the
+                    // caller keeps alive for the call.
+                    ffi_args_with_types += &format!(", {name}: *const {ty}");
+                    args_no_types += &format!(", {name}");
+                } else if let Type::Borrow(mutability, _, _) = ty {
+                    // Borrows are passed as pointers. Borrows of bound classes (`&NSString`,
+                    // from this macro or any other) are passed as the instance pointer
+                    // instead of a pointer to the wrapper, which `BorrowedArg` picks
+                    // between with the type's `BoundClass` impl.
+                    let (ptr, borrow) = match mutability {
+                        Mutability::Immut => (
+                            "*const ()",
+                            format!("core::ptr::from_ref({name}).cast_mut()"),
+                        ),
+                        Mutability::Mut => ("*mut ()", format!("core::ptr::from_mut({name})")),
+                    };
+                    ffi_args_with_types += &format!(", {name}: {ptr}");
+                    args_no_types += &format!(
+                        ", {{
+                            use objective_rust::ffi::{{BorrowedClass as _, BorrowedValue as _}};
+                            (&objective_rust::ffi::BorrowedArg({borrow})).as_arg()
+                        }}"
+                    );
+                } else if ty.is_bool() {
+                    // Rust's `bool` isn't guaranteed to match `BOOL`'s ABI, so convert it.
+                    ffi_args_with_types += &format!(", {name}: objective_rust::ObjcBool");
+                    args_no_types += &format!(", objective_rust::ObjcBool::from({name})");
+                } else {
+                    // Bound classes are `#[repr(transparent)]`, so `Self` can be passed as is.
+                    ffi_args_with_types += &format!(", {name}: {}", ty.format(Some(self_ty)));
+                    args_no_types += &format!(", {name}");
+                }
+            }
+
+            // The list's first element is the method's last fixed argument (like
+            // `initWithObjects:`'s `firstObj`), so it's passed as one; on arm64, fixed
+            // and variadic arguments are passed differently.
+            let mut variadic_nil = "";
+            if let Some(Argument { name, ty, .. }) = variadic_arg {
+                let self_ty = ty.self_ty(class_name, &instance_name);
+                args_with_types += &format!(", {name}: {}", ty.format(Some(self_ty)));
+                if let Type::Slice(ty, _) = ty {
+                    ffi_args_with_types +=
+                        &format!(", {name}_first: {}, ...", ty.format(Some(self_ty)));
+                    variadic_nil = match &**ty {
+                        Type::Pointer(Mutability::Immut, _, _) => "core::ptr::null()",
+                        _ => "core::ptr::null_mut()",
+                    };
+                }
+            }
+            if let Some(Argument { name, .. }) = error_arg {
+                ffi_args_with_types += &format!(", {name}: *mut *mut ()");
+                args_no_types += ", &mut error";
+            }
+            if let (true, Some(ret)) = (*out_struct, return_type) {
+                // The struct is created by the wrapper, and its address is passed as the
+                // last argument for the method to fill in.
+                ffi_args_with_types += &format!(", out_struct: *mut {ret}");
+                args_no_types += ", &mut out_struct";
+            }
+            // Likewise, each of the values in an `#[out_params]` tuple is a local whose
+            // address is passed to the method.
+            let out_values = match (out_params, return_type) {
+                (true, Some(Type::Tuple(values, _))) => values.as_slice(),
+                _ => &[],
+            };
+            for (idx, value) in out_values.iter().enumerate() {
+                let value = value.format(Some(&instance_name));
+                ffi_args_with_types += &format!(", out_{idx}: *mut {value}");
+                args_no_types += &format!(", &mut out_{idx}");
+            }
+
+            // `init` methods and others returning `instancetype` move ownership from `self`
+            // to the returned instance.
+            let returns_self_from_owned = *self_reference == SelfReference::Owned
+                && return_type
+                    .as_ref()
+                    .is_some_and(Type::is_self_or_optional_self);
+            let (ffi_return_type, return_type_formatted) =
+                if let (true, Some(ret)) = (*out_struct, return_type) {
+                    (String::new(), format!("-> {ret}"))
+                } else if let (true, Some(ret)) = (*out_params, return_type) {
+                    (
+                        String::new(),
+                        format!("-> {}", ret.format(Some(&instance_name))),
+                    )
+                } else if let (true, Some(ret)) = (*singleton, return_type) {
+                    let ret = match ret {
+                        Type::Option(_, _) => format!("-> Option<{class_name}Ref<'static>>"),
+                        _ => format!("-> {class_name}Ref<'static>"),
+                    };
+                    (format!("-> *mut {instance_name}"), ret)
+                } else if returns_self_from_owned {
+                    // Consuming methods return a new wrapper for the returned instance.
+                    let ret = match return_type {
+                        Some(Type::Option(_, _)) => "-> Option<Self>",
+                        _ => "-> Self",
+                    };
+                    (format!("-> *mut {instance_name}"), ret.to_string())
+                } else if let (Some(_), Some(ret)) = (cf_return, return_type) {
+                    // Toll-free bridged returns are wrapped in the bound class.
+                    (
+                        format!("-> Option<{}>", ret.format(Some(class_name))),
+                        format!("-> Option<{ret}>"),
+                    )
+                } else if let (true, Some(ret @ Type::Pointer(_, pointee, _))) =
+                    (*nullable, return_type)
+                {
+                    (
+                        format!("-> {}", ret.format(Some(&instance_name))),
+                        format!(
+                            "-> Option<core::ptr::NonNull<{}>>",
+                            pointee.format(Some(&instance_name))
+                        ),
+                    )
+                } else if let (true, Some(ret)) = (*borrowed_return, return_type) {
+                    (
+                        "-> *mut ()".to_string(),
+                        format!("-> Option<{}Ref<'_>>", ret.format(Some(class_name))),
+                    )
+                } else if *cached {
+                    ("-> *mut ()".to_string(), "-> &str".to_string())
+                } else if let (true, Some(ret)) = (method.returns_borrowed_string(), return_type) {
+                    ("-> *mut ()".to_string(), format!("-> {ret}<'_>"))
+                } else if let (Some(handle), Some(ret)) = (handle_return, return_type) {
+                    (
+                        format!("-> {}", ret.format(Some(&instance_name))),
+                        format!("-> Option<{handle}>"),
+                    )
+                } else if let (true, Some(ret)) = (*bool_is_success, return_type) {
+                    let ffi_ret = match ret.is_bool() {
+                        true => "objective_rust::ObjcBool".to_string(),
+                        false => ret.to_string(),
+                    };
+                    (format!("-> {ffi_ret}"), "-> Result<(), ()>".to_string())
+                } else if return_type.as_ref().is_some_and(Type::is_bool) {
+                    (
+                        "-> objective_rust::ObjcBool".to_string(),
+                        match returns_as {
+                            Some(ty) => format!("-> {ty}"),
+                            None => "-> bool".to_string(),
+                        },
+                    )
+                } else if let (Some(ty), Some(ret)) = (returns_as, return_type) {
+                    (format!("-> {ret}"), format!("-> {ty}"))
+                } else if let Some(ret) = return_type {
+                    // Like arguments, `Option<Self>` is the wrapper, not the instance.
+                    let self_ty = match ret {
+                        Type::Option(_, _) => ret.self_ty(class_name, &instance_name),
+                        _ => &instance_name,
+                    };
+                    let ret = format!("-> {}", ret.format(Some(self_ty)));
+                    (ret.clone(), ret)
+                } else {
+                    (String::new(), String::new())
+                };
+
+            // Constructors are sent to the instance they allocate, not the class.
+            let instance_ty = match self_reference {
+                SelfReference::None if *constructor => format!("*mut {instance_name}"),
+                SelfReference::None => "objective_rust::ffi::Class".into(),
+                SelfReference::Mutable | SelfReference::Owned => {
+                    format!("*mut {instance_name}")
+                }
+                SelfReference::Immutable => format!("*const {instance_name}"),
+            };
+
+            // Exceptions can only unwind through functions declared with `C-unwind`.
+            let abi = match throws {
+                true => "C-unwind",
+                false => "C",
+            };
+            // Variadic function pointers can only be called unsafely.
+            let unsafety = match variadic {
+                true => "unsafe",
+                false => "",
+            };
+            let c_fn = format!(
+                "
+                {unsafety} extern \"{abi}\" fn(
+                    instance: {instance_ty},
+                    sel: objective_rust::ffi::Selector
+                    {ffi_args_with_types}
+                ){ffi_return_type}
+                "
+            );
+
+            vtable_entries +=
+                &format!("{vtable_cfg} {name}: ({c_fn}, objective_rust::ffi::Selector),");
+            // Large structs are returned through `objc_msgSend_stret` on some targets.
+            let msg_send_ret = ffi_return_type.trim().strip_prefix("->").unwrap_or("()");
+            // In debug builds, the signature is checked against the method's real one.
+            // Variadic arguments aren't part of the method's type encoding, and
+            // `#[expose_cmd]` methods can be called with any selector, so they can't be.
+            let encoding = if *variadic || *expose_cmd {
+                None
+            } else {
+                let mut encoding = match (*out_struct || *out_params, return_type) {
+                    (false, Some(ret)) => ret.encoding().to_string(),
+                    _ => "v".to_string(),
+                };
+                encoding += "@:";
+                for arg in args {
+                    encoding += match &arg.ty {
+                        _ if arg.consumed => "@",
+                        Type::Slice(_, _) => "^vQ",
+                        ty => ty.encoding(),
+                    };
+                }
+                if error_arg.is_some() {
+                    encoding += "^@";
+                }
+                if *out_struct {
+                    encoding += "^v";
+                }
+                for _ in out_values {
+                    encoding += "^v";
+                }
+
+                Some(encoding)
+            };
+            // Both `load` and `try_load` have the class and metaclass in locals.
+            let target = match self_reference {
+                SelfReference::None if !*constructor => "metaclass",
+                _ => "class",
+            };
+            let check_encoding = match &encoding {
+                Some(encoding) => format!(
+                    r#"
+                    #[cfg(debug_assertions)]
+                    objective_rust::ffi::check_method_encoding({target}, sel, "{encoding}");
+                    "#
+                ),
+                None => String::new(),
+            };
+            vtable_setup += &format!(
+                r#"
+                {vtable_cfg}
+                let {name} = {{
+                    let sel = objective_rust::ffi::get_selector("{selector}").unwrap();
+                    {check_encoding}
+                    // Calls go through `objc_msgSend`, so the runtime picks the receiver's
+                    // own implementation (like a subclass' override) for every call.
+                    let msg_send = objective_rust::ffi::get_msg_send_for::<{msg_send_ret}>();
+                    let func = unsafe {{ core::mem::transmute(msg_send) }};
+
+                    (func, sel)
+                }};
+                "#
+            );
+            vtable_constructor += &format!("{vtable_cfg} {name},");
+            // `#[expose_cmd]` methods are called with whichever selector the caller passes.
+            if !*expose_cmd {
+                // `try_load` returns the mismatch instead of panicking like `load` does.
+                let check_encoding = match &encoding {
+                    Some(encoding) => format!(
+                        r#"
+                        #[cfg(debug_assertions)]
+                        if !objective_rust::ffi::method_encoding_matches({target}, sel, "{encoding}") {{
+                            return Err(objective_rust::ffi::LoadError::Encoding {{
+                                class: "{class_name}",
+                                selector: "{selector}",
+                            }});
+                        }}
+                        "#
+                    ),
+                    None => String::new(),
+                };
+                load_checks += &format!(
+                    r#"
+                    {vtable_cfg}
+                    {{
+                        let missing = objective_rust::ffi::LoadError::Selector {{
+                            class: "{class_name}",
+                            selector: "{selector}",
+                        }};
+                        let sel = objective_rust::ffi::get_selector("{selector}").ok_or(missing.clone())?;
+                        if !objective_rust::ffi::class_responds_to({target}, sel) {{
+                            return Err(missing);
+                        }}
+                        {check_encoding}
+                    }}
+                    "#
+                );
+            }
+
+            let fn_args = if *self_reference == SelfReference::None && args_with_types.len() > 2 {
+                // skip over the `, `
+                &args_with_types[2..]
+            } else {
+                args_with_types.as_str()
+            };
+            let instance_ptr = match self_reference {
+                SelfReference::None if *constructor => "this",
+                // Class methods are sent to the class object itself
+                SelfReference::None => "vtable.class",
+                SelfReference::Owned => "this.0.as_ptr()",
+                SelfReference::Mutable | SelfReference::Immutable => "self.0.as_ptr()",
+            };
+            let trace = if cfg!(feature = "trace") {
+                let class_method = *self_reference == SelfReference::None && !*constructor;
+                format!(
+                    r#"objective_rust::trace::dispatch("{class_name}", "{selector}", {class_method});"#
+                )
+            } else {
+                String::new()
+            };
+            let sel = if *expose_cmd {
+                "cmd".to_string()
+            } else {
+                format!("vtable.{name}.1")
+            };
+            let call = if let Some(Argument { name: list, .. }) = variadic_arg {
+                // C variadic calls need every argument written out, so there's a call for
+                // each length the list can be, each ending with nil. An empty list is just
+                // the nil, passed as the first element.
+                let calls = (0..=VARIADIC_MAX)
+                    .map(|len| {
+                        let spread = match len {
+                            0 => format!(", {variadic_nil}"),
+                            len => (0..len)
+                                .map(|idx| format!(", {list}[{idx}]"))
+                                .chain([", core::ptr::null_mut::<()>()".to_string()])
+                                .collect(),
+                        };
+                        format!("{len} => func({instance_ptr}, sel{args_no_types}{spread}),")
+                    })
+                    .collect::<String>();
+                format!(
+                    r#"
+                    {class_name}VTable::with(|vtable| {{
+                        let func = vtable.{name}.0;
+                        let sel = {sel};
+
+                        unsafe {{
+                            match {list}.len() {{
+                                {calls}
+                                len => panic!(
+                                    "`{selector}` was called with {{len}} variadic arguments, but only up to {VARIADIC_MAX} are supported"
+                                ),
+                            }}
+                        }}
+                    }})
+                    "#
+                )
+            } else {
+                format!(
+                    "
+                    {class_name}VTable::with(|vtable| {{
+                        let func = vtable.{name}.0;
+                        let sel = {sel};
+
+                        func({instance_ptr}, sel{args_no_types})
+                    }})
+                    "
+                )
+            };
+            let body = if let (true, Some(ret)) = (*out_struct, return_type) {
+                // Starting from the default means fields the method doesn't set are
+                // still valid.
+                format!(
+                    "
+                    let mut out_struct = <{ret}>::default();
+                    {call};
+                    out_struct
+                    "
+                )
+            } else if *out_params {
+                // Like `#[out_struct]`, the values start out as zero (or null), so they're
+                // valid even if the method doesn't set them.
+                let mut locals = String::new();
+                let mut values = String::new();
+                for (idx, value) in out_values.iter().enumerate() {
+                    let initial = match value {
+                        Type::Pointer(Mutability::Mut, _, _) => "core::ptr::null_mut()",
+                        Type::Pointer(Mutability::Immut, _, _) => "core::ptr::null()",
+                        _ => "Default::default()",
+                    };
+                    let value = value.format(Some(&instance_name));
+                    locals += &format!("let mut out_{idx}: {value} = {initial};");
+                    values += &format!("out_{idx},");
+                }
+                format!(
+                    "
+                    {locals}
+                    {call};
+                    ({values})
+                    "
+                )
+            } else if let (true, Some(ret)) = (*singleton, return_type) {
+                // Shared instances live for the rest of the program, and the caller
+                // doesn't own a reference to them, so they're borrowed forever.
+                match ret {
+                    Type::Option(_, _) => format!(
+                        "core::ptr::NonNull::new({call}).map(|ptr| unsafe {{ {class_name}Ref::from_raw(ptr) }})"
+                    ),
+                    _ => format!(
+                        r#"
+                        let ptr = core::ptr::NonNull::new({call}).expect("`{selector}` returned nil");
+                        unsafe {{ {class_name}Ref::from_raw(ptr) }}
+                        "#
+                    ),
+                }
+            } else if returns_self_from_owned {
+                // Failable initialisers return `Option<Self>`. When they fail, they
+                // release the instance themselves, so nothing needs to be released here.
+                let wrap = match return_type {
+                    Some(Type::Option(_, _)) => {
+                        "core::ptr::NonNull::new(ptr).map(Self)".to_string()
+                    }
+                    _ => format!(
+                        r#"Self(core::ptr::NonNull::new(ptr).expect("`{selector}` returned nil"))"#
+                    ),
+                };
+                // The method returns the instance it was called on (or, for `init`
+                // methods, the instance replacing it), so ownership moves from `self`
+                // to the returned pointer without any retains or releases.
+                format!(
+                    r#"
+                    let this = core::mem::ManuallyDrop::new(self);
+                    let ptr = {call};
+                    {wrap}
+                    "#
+                )
+            } else if let Some(cf_return) = cf_return {
+                // Follow CoreFoundation's ownership rules: functions with `Create` or
+                // `Copy` in their name return a reference the caller owns, and others
+                // (`Get` functions) don't, so the wrapper needs its own reference.
+                // Bound classes are `#[repr(transparent)]`, so `Option<Class>` has the
+                // same layout as a nullable instance pointer.
+                match cf_return {
+                    CfReturn::Create => call,
+                    CfReturn::Get => format!(
+                        "
+                        {call}.inspect(|obj| unsafe {{
+                            objective_rust::ffi::retain(obj.into_raw());
+                        }})
+                        "
+                    ),
+                }
+            } else if let Some(handle) = handle_return {
+                format!("core::ptr::NonNull::new({call} as *mut ()).map({handle})")
+            } else if *bool_is_success {
+                format!("if bool::from({call}) {{ Ok(()) }} else {{ Err(()) }}")
+            } else if return_type.as_ref().is_some_and(Type::is_bool) {
+                match returns_as {
+                    Some(ty) => format!("{ty}::from(bool::from({call}))"),
+                    None => format!("bool::from({call})"),
+                }
+            } else if let Some(ty) = returns_as {
+                format!("{ty}::from({call})")
+            } else if *nullable {
+                format!("core::ptr::NonNull::new({call} as *mut _)")
+            } else if let (true, Some(ret)) = (*borrowed_return, return_type) {
+                // The object belongs to `self`, and the returned reference borrows `self`.
+                format!(
+                    "
+                    core::ptr::NonNull::new({call})
+                        .map(|ptr| unsafe {{ {}Ref::from_raw(ptr.cast()) }})
+                    ",
+                    ret.format(Some(class_name))
+                )
+            } else if *cached {
+                // The string's copied the first time, and the copy lives as long as the
+                // instance does, so the returned `&str` borrows `self`.
+                format!(
+                    "
+                    static KEY: u8 = 0;
+                    unsafe {{
+                        objective_rust::ffi::cached_string(self.0, &KEY, || {{
+                            objective_rust::ffi::NSStr::from_ptr({call}).as_str().to_owned()
+                        }})
+                    }}
+                    "
+                )
+            } else if method.returns_borrowed_string() {
+                // The string belongs to `self`, and the returned `NSStr` borrows `self`.
+                format!("unsafe {{ objective_rust::ffi::NSStr::from_ptr({call}) }}")
+            } else {
+                call
+            };
+            // Other consuming methods (like `autorelease`) take over the wrapper's
+            // reference, so it isn't released when `self` goes out of scope.
+            let body = match *self_reference == SelfReference::Owned && !returns_self_from_owned {
+                true => format!("let this = core::mem::ManuallyDrop::new(self); {body}"),
+                false => body,
+            };
+            // Constructors allocate the instance first. The `init` method takes over the
+            // allocated instance's reference, and returns an owned reference to the
+            // initialised instance (or releases it and returns nil).
+            let body = match constructor {
+                true => format!(
+                    r#"
+                    let class = Self::get_objc_class();
+                    let class = objective_rust::ffi::Instance::from_ptr(
+                        core::ptr::NonNull::new(class.as_ptr()).unwrap()
+                    );
+                    let alloc = objective_rust::ffi::get_selector("alloc").unwrap();
+                    let this: *mut {instance_name} =
+                        unsafe {{ objective_rust::ffi::msg_send(class, alloc, ()) }};
+                    if this.is_null() {{
+                        None
+                    }} else {{
+                        {body}
+                    }}
+                    "#
+                ),
+                false => body,
+            };
+            let (return_type_formatted, body) = match return_type_formatted
+                .strip_prefix("-> Option<")
+                .and_then(|ret| ret.strip_suffix('>'))
+            {
+                Some(ret) if *nil_is_err => (
+                    format!("-> Result<{ret}, ()>"),
+                    format!("({{ {body} }}).ok_or(())"),
+                ),
+                _ => (return_type_formatted, body),
+            };
+            let (return_type_formatted, body) = if *error_param {
+                // Cocoa methods signal failure with their return value (nil or `NO`), and
+                // only set the error when they fail. Methods that return something else
+                // can only be checked by whether the error was set.
+                let ret = return_type_formatted.strip_prefix("-> ").unwrap_or("()");
+                let (ok, check) = if ret == "bool" {
+                    ("()", "if value { Ok(()) }")
+                } else if let Some(inner) = ret
+                    .strip_prefix("Option<")
+                    .and_then(|ret| ret.strip_suffix('>'))
+                {
+                    (inner, "if let Some(value) = value { Ok(value) }")
+                } else if ret.starts_with('*') {
+                    (ret, "if !value.is_null() { Ok(value) }")
+                } else {
+                    (ret, "if error.is_null() { Ok(value) }")
+                };
+                (
+                    format!("-> Result<{ok}, objective_rust::ffi::NSError>"),
+                    format!(
+                        "
+                        let mut error: *mut () = core::ptr::null_mut();
+                        #[allow(clippy::let_unit_value)]
+                        let value = {{ {body} }};
+                        {check} else {{
+                            Err(unsafe {{ objective_rust::ffi::NSError::from_out_param(error) }})
+                        }}
+                        "
+                    ),
+                )
+            } else {
+                (return_type_formatted, body)
+            };
+            let (return_type_formatted, body) = match throws {
+                true => {
+                    let ret = return_type_formatted.strip_prefix("-> ").unwrap_or("()");
+                    (
+                        format!("-> Result<{ret}, objective_rust::ffi::Exception>"),
+                        format!("objective_rust::ffi::catch_exception(|| {{ {body} }})"),
+                    )
+                }
+                false => (return_type_formatted, body),
+            };
+            // These are thin wrappers, so let them inline into other crates (bindings
+            // are often in their own library crate), unless the user said otherwise.
+            let inline = match attributes.iter().any(|attr| attr.starts_with("#[inline")) {
+                true => "",
+                false => "#[inline]",
+            };
+            let pool_check = match needs_pool {
+                true => format!(
+                    r#"
+                    #[cfg(debug_assertions)]
+                    assert!(
+                        objective_rust::ffi::autorelease_pool_active(),
+                        "`{class_name}::{name}` returns an autoreleased object, so it has to be called inside an `AutoreleasePool`"
+                    );
+                    "#
+                ),
+                false => String::new(),
+            };
+            struct_fns += &format!(
+                "
+                {fn_cfg}
+                {fn_attributes}
+                {inline}
+                pub fn {name}({self_reference}{fn_args}){return_type_formatted} {{
+                    {pool_check}
+                    {trace}
+                    {body}
+                }}
+                "
+            );
+        }
+
+        let get_class = match &self.superclass {
+            None => format!(r#"objective_rust::ffi::get_class("{class_name}").unwrap()"#),
+            // Classes defined from Rust are registered the first time they're used.
+            Some(superclass) => {
+                let builder = match registrations.is_empty() {
+                    true => "builder",
+                    false => "mut builder",
+                };
+                format!(
+                    r#"
+                    {{
+                        static REGISTER: std::sync::Once = std::sync::Once::new();
+                        REGISTER.call_once(|| {{
+                            let superclass = objective_rust::ffi::get_class("{superclass}")
+                                .expect("`{class_name}`'s superclass, `{superclass}`, isn't loaded");
+                            let {builder} =
+                                objective_rust::ffi::ClassBuilder::new("{class_name}", superclass)
+                                    .expect("a class named `{class_name}` already exists");
+                            {registrations}
+                            builder.register();
+                        }});
+
+                        objective_rust::ffi::get_class("{class_name}").unwrap()
+                    }}
+                    "#
+                )
+            }
+        };
+
+        // Classes defined from Rust only need their superclass to be loaded.
+        let loaded_class = self.superclass.as_ref().unwrap_or(class_name);
+
+        write!(
+            f,
+            r#"
+            {cfg}
+            {allow}
+            struct {class_name}VTable {{
+                class: objective_rust::ffi::Class,
+                metaclass: objective_rust::ffi::Class,
+                {vtable_entries}
+            }}
+            {cfg}
+            {allow}
+            impl {class_name}VTable {{
+                fn class() -> objective_rust::ffi::Class {{
+                    {get_class}
+                }}
+
+                fn load() -> Self {{
+                    let class = Self::class();
+                    let metaclass = objective_rust::ffi::get_metaclass("{class_name}").unwrap();
+
+                    {vtable_setup}
+
+                    Self {{
+                        class,
+                        metaclass,
+                        {vtable_constructor}
+                    }}
+                }}
+
+                #[inline(always)]
+                fn with<R>(f: impl FnOnce(&Self) -> R) -> R {{
+                    {vtable_storage}
+                }}
+            }}
+
+            /// An opaqe type representing an Objective-C instance of [`{class_name}`].
+            /// Class constructors should return a pointer to this type, and [`{class_name}`]
+            /// stores a pointer to this type.
+            {cfg}
+            {allow}
+            pub struct {instance_name}(std::marker::PhantomData<()>);
+
+            {cfg}
+            {allow}
+            {attributes}
+            #[repr(transparent)]
+            pub struct {class_name}(std::ptr::NonNull<{instance_name}>);
+
+            {cfg}
+            {allow}
+            impl {class_name} {{
+                /// Attempts to create a new `{class_name}` from a pointer.
+                ///
+                /// The new `{class_name}` owns a reference to the instance, and releases
+                /// it when dropped. This is fine for instances that were allocated but
+                /// never initialised, too: Objective-C zeroes freshly allocated instances,
+                /// and `dealloc` has to handle that state.
+                ///
+                /// # Safety
+                /// - The pointer must point to a valid `{instance_name}`.
+                /// - The pointer must be valid for at least as long as this instance lives.
+                pub unsafe fn from_raw(ptr: core::ptr::NonNull<{instance_name}>) -> Self {{
+                    Self(ptr)
+                }}
+
+                /// Get the underlying pointer to the actual Objective-C class instance.
+                pub fn into_raw(&self) -> core::ptr::NonNull<{instance_name}> {{
+                    self.0
+                }}
+
+                /// Loads the class' vtable now, instead of when it's first used, so that the
+                /// first method call doesn't have to look up the class and every selector.
+                /// Thread-local vtables are only loaded for the current thread.
+                pub fn preload() {{
+                    {class_name}VTable::with(|_| ());
+                }}
+
+                /// Loads the class' vtable now, like [`{class_name}::preload`], but checks
+                /// that the class is loaded and responds to every bound method's selector
+                /// first (and, in debug builds, that each method is bound with its real
+                /// signature). This can be used to validate bindings at startup, instead
+                /// of panicking (or hitting an unrecognised selector) on first use.
+                pub fn try_load() -> Result<(), objective_rust::ffi::LoadError> {{
+                    if objective_rust::ffi::get_class("{loaded_class}").is_none() {{
+                        return Err(objective_rust::ffi::LoadError::Class("{loaded_class}"));
+                    }}
+                    // The checks run before the vtable is loaded, since loading it panics
+                    // on mismatched signatures in debug builds.
+                    #[allow(unused_variables)]
+                    let class = {class_name}VTable::class();
+                    #[allow(unused_variables)]
+                    let metaclass = objective_rust::ffi::get_metaclass("{class_name}").unwrap();
+                    {load_checks}
+                    Self::preload();
+
+                    Ok(())
+                }}
+
+                /// Returns the Objective-C class this struct binds to.
+                pub fn get_objc_class() -> objective_rust::ffi::Class {{
+                    {class_name}VTable::with(|vtable| vtable.class.clone())
+                }}
+
+                /// Returns thie Objective-C metaclass for the class this struct binds to.
+                pub fn get_objc_metaclass() -> objective_rust::ffi::Class {{
+                    {class_name}VTable::with(|vtable| vtable.metaclass.clone())
+                }}
+
+                /// Returns the name of the class this instance actually is at runtime, which
+                /// may be a subclass of this class.
+                pub fn runtime_class_name(&self) -> String {{
+                    let instance = objective_rust::ffi::Instance::from_ptr(self.0);
+                    let class = objective_rust::ffi::get_object_class(instance);
+
+                    objective_rust::ffi::class_name(class).to_string()
+                }}
+
+                /// Checks if this instance is an instance of `class`, or of one of its
+                /// subclasses, with `isKindOfClass:`.
+                pub fn is_kind_of(&self, class: objective_rust::ffi::Class) -> bool {{
+                    let instance = objective_rust::ffi::Instance::from_ptr(self.0);
+                    let sel = objective_rust::ffi::get_selector("isKindOfClass:").unwrap();
+                    let is_kind: objective_rust::ObjcBool =
+                        unsafe {{ objective_rust::ffi::msg_send(instance, sel, (class,)) }};
+
+                    is_kind.into()
+                }}
+
+                /// Checks if this instance is exactly an instance of `class` (not a
+                /// subclass), with `isMemberOfClass:`.
+                pub fn is_member_of(&self, class: objective_rust::ffi::Class) -> bool {{
+                    let instance = objective_rust::ffi::Instance::from_ptr(self.0);
+                    let sel = objective_rust::ffi::get_selector("isMemberOfClass:").unwrap();
+                    let is_member: objective_rust::ObjcBool =
+                        unsafe {{ objective_rust::ffi::msg_send(instance, sel, (class,)) }};
+
+                    is_member.into()
+                }}
+
+                /// Checks if the class adopts `protocol` (or inherits it from a superclass),
+                /// like `conformsToProtocol:`.
+                pub fn conforms_to(protocol: objective_rust::ffi::Protocol) -> bool {{
+                    objective_rust::ffi::class_conforms_to(Self::get_objc_class(), protocol)
+                }}
+
+                /// Casts this instance to another bound class, if it's an instance of that
+                /// class (or one of its subclasses). Otherwise, it's returned unchanged.
+                pub fn try_cast<T: objective_rust::ffi::BoundClass>(self) -> Result<T, Self> {{
+                    if !self.is_kind_of(T::get_objc_class()) {{
+                        return Err(self);
+                    }}
+
+                    let this = core::mem::ManuallyDrop::new(self);
+                    Ok(unsafe {{ T::from_raw(this.0.cast()) }})
+                }}
+
+                /// Swaps the implementations of two of this class' instance methods, so
+                /// calling `original` runs `replacement`'s code, and the other way around.
+                /// Returns `false` (and swaps nothing) if either method doesn't exist.
+                ///
+                /// # Safety
+                /// This changes the methods for the whole process; see
+                /// [`objective_rust::ffi::exchange_implementations`].
+                pub unsafe fn swizzle(original: &str, replacement: &str) -> bool {{
+                    let class = Self::get_objc_class();
+                    let method = |name| {{
+                        let sel = objective_rust::ffi::get_selector(name)?;
+                        objective_rust::ffi::get_instance_method(class, sel)
+                    }};
+                    let (Some(original), Some(replacement)) = (method(original), method(replacement)) else {{
+                        return false;
+                    }};
+
+                    objective_rust::ffi::exchange_implementations(original, replacement);
+                    true
+                }}
+
+                /// Checks if instances of this class respond to the selector `name`, so
+                /// methods that only exist on some OS versions can be checked before
+                /// they're called.
+                pub fn responds_to(name: &str) -> bool {{
+                    let Some(sel) = objective_rust::ffi::get_selector(name) else {{
+                        return false;
+                    }};
+
+                    objective_rust::ffi::class_responds_to(Self::get_objc_class(), sel)
+                }}
+
+                {struct_fns}
+            }}
+            {ivar_checks}
+            {cfg}
+            {allow}
+            impl Drop for {class_name} {{
+                fn drop(&mut self) {{
+                    {on_drop}
+                    // `objc_release` handles tagged pointers, which don't have a real
+                    // object (or `release` implementation) behind them.
+                    unsafe {{ objective_rust::ffi::release(self.0) }}
+                }}
+            }}
+            /// A borrowed [`{class_name}`], for objects the caller doesn't own (like
+            /// an object's property). It isn't released when it's dropped, and can't
+            /// outlive the object it was borrowed from. It derefs to [`{class_name}`],
+            /// so methods that take `&self` can be called through it.
+            {cfg}
+            {allow}
+            #[repr(transparent)]
+            #[derive(Clone, Copy)]
+            pub struct {class_name}Ref<'a>(
+                core::ptr::NonNull<{instance_name}>,
+                core::marker::PhantomData<&'a {class_name}>,
+            );
+
+            {cfg}
+            {allow}
+            impl {class_name}Ref<'_> {{
+                /// Creates a borrowed `{class_name}` from a pointer, without taking
+                /// ownership of the instance.
+                ///
+                /// # Safety
+                /// - The pointer must point to a valid `{instance_name}`.
+                /// - The instance must stay alive for as long as the returned reference.
+                pub unsafe fn from_raw(ptr: core::ptr::NonNull<{instance_name}>) -> Self {{
+                    Self(ptr, core::marker::PhantomData)
+                }}
+
+                /// Retains the instance, returning an owned `{class_name}` that can
+                /// outlive this reference.
+                pub fn retain(&self) -> {class_name} {{
+                    {class_name}(unsafe {{ objective_rust::ffi::retain(self.0) }})
+                }}
+            }}
+            {cfg}
+            {allow}
+            impl core::ops::Deref for {class_name}Ref<'_> {{
+                type Target = {class_name};
+
+                fn deref(&self) -> &{class_name} {{
+                    // `{class_name}` is a `#[repr(transparent)]` wrapper around the same
+                    // pointer. It's only borrowed, so it's never dropped (or released).
+                    unsafe {{ &*(&self.0 as *const core::ptr::NonNull<{instance_name}>).cast() }}
+                }}
+            }}
+            {cfg}
+            {allow}
+            impl From<{class_name}> for core::ptr::NonNull<{instance_name}> {{
+                /// Converts the wrapper into its instance pointer, giving up ownership.
+                /// The pointer owns the reference the wrapper held, so it's up to the
+                /// caller to release it (or hand it to `from_raw` again).
+                fn from(value: {class_name}) -> Self {{
+                    core::mem::ManuallyDrop::new(value).0
+                }}
+            }}
+            {cfg}
+            {allow}
+            impl From<objective_rust::ffi::Id<{instance_name}>> for {class_name} {{
+                /// Wraps an owned reference, without retaining it again.
+                fn from(value: objective_rust::ffi::Id<{instance_name}>) -> Self {{
+                    Self(value.into_raw())
+                }}
+            }}
+            {cfg}
+            {allow}
+            impl From<{class_name}> for objective_rust::ffi::Id<{instance_name}> {{
+                /// Converts the wrapper into an `Id`, which takes over its reference.
+                fn from(value: {class_name}) -> Self {{
+                    unsafe {{ Self::from_raw(core::mem::ManuallyDrop::new(value).0) }}
+                }}
+            }}
+            {cfg}
+            {allow}
+            unsafe impl objective_rust::ffi::BoundClass for {class_name} {{
+                type Instance = {instance_name};
+
+                fn get_objc_class() -> objective_rust::ffi::Class {{
+                    {class_name}::get_objc_class()
+                }}
+
+                unsafe fn from_raw(ptr: core::ptr::NonNull<{instance_name}>) -> Self {{
+                    {class_name}::from_raw(ptr)
+                }}
+            }}
+            {cfg}
+            {allow}
+            impl Clone for {class_name} {{
+                /// Retains the instance. Objective-C objects are reference types, so the
+                /// clone refers to the same object: changes made through one are visible
+                /// through the other.
+                fn clone(&self) -> Self {{
+                    Self(unsafe {{ objective_rust::ffi::retain(self.0) }})
+                }}
+            }}
+            {debug}
+            {comparable}
+            {derive_eq}
+            {derive_hash}
+            {thread_safe}
+            "#,
+        )
+    }
+}
+
+impl Class {
+    /// Generates the methods implemented in Rust. They're generated separately from the
+    /// rest of the class, because the class is generated as a string, and that would
+    /// lose the spans of the code in their bodies.
+    fn implemented_methods(&self) -> TokenStream {
+        let class_name = &self.name;
+        let instance_name = self.instance_name();
+        let mut methods = TokenStream::new();
+
+        for method in &self.methods {
+            let Some(body) = &method.body else {
+                continue;
+            };
+            let mut args = String::new();
+            for Argument { name, ty, .. } in &method.args {
+                args += &format!(", {name}: {}", ty.format(Some(&instance_name)));
+            }
+            let args = match method.self_reference {
+                SelfReference::None => args.strip_prefix(", ").unwrap_or_default(),
+                _ => &args,
+            };
+            let return_type = match &method.return_type {
+                Some(ty) => format!("-> {}", ty.format(Some(&instance_name))),
+                None => String::new(),
+            };
+            let signature = format!(
+                "{}{} pub fn {}({}{args}) {return_type}",
+                method.cfg.concat(),
+                method.attributes.concat(),
+                method.name,
+                method.self_reference,
+            );
+
+            methods.extend(signature.parse::<TokenStream>().unwrap());
+            methods.extend([TokenTree::Group(body.clone())]);
+
+            if let Some(superclass) = &self.superclass {
+                let super_call = method.super_call(&instance_name, superclass);
+                methods.extend(super_call.parse::<TokenStream>().unwrap());
+            }
+        }
+        if methods.is_empty() {
+            return methods;
+        }
+
+        let mut result = format!(
+            "{} #[allow(dead_code, non_snake_case, deprecated)] impl {class_name}",
+            self.cfg.concat()
+        )
+        .parse::<TokenStream>()
+        .unwrap();
+        result.extend([TokenTree::Group(Group::new(Delimiter::Brace, methods))]);
+
+        result
+    }
+}
+
+impl Function {
+    /// Generates the code that adds a method implemented in Rust to its class, when
+    /// it's registered. The method is added with an `extern "C-unwind"` trampoline,
+    /// which Objective-C calls with the receiver and selector, and which calls the
+    /// method, catching any panic with `panic_strategy`.
+    fn registration(
+        &self,
+        class_name: &str,
+        instance_name: &str,
+        panic_strategy: PanicStrategy,
+    ) -> String {
+        let Self {
+            name,
+            return_type,
+            args,
+            self_reference,
+            selector,
+            cfg,
+            ..
+        } = self;
+        let selector = selector.as_ref().unwrap_or(name);
+        let cfg = cfg
+            .iter()
+            .filter(|cfg| cfg.starts_with("#[cfg("))
+            .map(String::as_str)
+            .collect::<String>();
+        let encoding = |ty: &Type| match ty {
+            Type::Pointer(_, ty, _) if matches!(&**ty, Type::Absolute(ty, _) if ty == "Self") => {
+                r#""@""#.to_string()
+            }
+            ty => format!(
+                "<{} as objective_rust::ffi::Encode>::ENCODING",
+                ty.format(Some(instance_name))
+            ),
+        };
+
+        let mut encodings = match return_type {
+            Some(ty) => encoding(ty),
+            None => r#""v""#.to_string(),
+        };
+        encodings += r#", "@:""#;
+        let mut ffi_args = String::new();
+        let mut call_args = String::new();
+        for Argument { name, ty, .. } in args {
+            encodings += &format!(", {}", encoding(ty));
+            if ty.is_bool() {
+                ffi_args += &format!(", {name}: objective_rust::ObjcBool");
+                call_args += &format!(", bool::from({name})");
+            } else {
+                ffi_args += &format!(", {name}: {}", ty.format(Some(instance_name)));
+                call_args += &format!(", {name}");
+            }
+        }
+
+        let (ffi_return, call) = match return_type {
+            Some(ty) if ty.is_bool() => (
+                "-> objective_rust::ObjcBool".to_string(),
+                "objective_rust::ObjcBool::from",
+            ),
+            Some(ty) => (format!("-> {}", ty.format(Some(instance_name))), ""),
+            None => (String::new(), ""),
+        };
+        let (receiver, setup, call_args, add_method) = match self_reference {
+            SelfReference::None => (
+                "_class: objective_rust::ffi::Class".to_string(),
+                String::new(),
+                call_args.strip_prefix(", ").unwrap_or_default().to_string(),
+                "add_class_method",
+            ),
+            _ => {
+                let (this, this_ref) = match self_reference {
+                    SelfReference::Mutable => ("mut this", "&mut this"),
+                    _ => ("this", "&this"),
+                };
+                (
+                    format!("this: *mut {instance_name}"),
+                    // The receiver is only borrowed, so it mustn't be released.
+                    format!(
+                        "
+                        let {this} = core::mem::ManuallyDrop::new(
+                            {class_name}(core::ptr::NonNull::new(this).unwrap())
+                        );
+                        "
+                    ),
+                    format!("{this_ref}{call_args}"),
+                    "add_method",
+                )
+            }
+        };
+
+        let panic_strategy = match panic_strategy {
+            PanicStrategy::Abort => "Abort",
+            PanicStrategy::Throw => "Throw",
+        };
+
+        // Panics can't unwind into Objective-C, so they're caught here. The function is
+        // `"C-unwind"` so the exception raised by `PanicStrategy::Throw` can unwind out
+        // of it.
+        format!(
+            r#"
+            {cfg}
+            extern "C-unwind" fn {name}(
+                {receiver},
+                _cmd: objective_rust::ffi::Selector
+                {ffi_args}
+            ) {ffi_return} {{
+                objective_rust::ffi::call_rust_method(
+                    objective_rust::ffi::PanicStrategy::{panic_strategy},
+                    "{class_name}",
+                    "{selector}",
+                    move || {{
+                        {setup}
+                        {call}({class_name}::{name}({call_args}))
+                    }},
+                )
+            }}
+            {cfg}
+            unsafe {{
+                let added = builder.{add_method}(
+                    objective_rust::ffi::get_selector("{selector}").unwrap(),
+                    {name} as *const (),
+                    &[{encodings}].concat(),
+                );
+                assert!(added, "`{class_name}` implements `{selector}` more than once");
+            }}
+            "#
+        )
+    }
+
+    /// Generates the method's declaration in a protocol's `Methods` trait.
+    fn trait_signature(&self) -> String {
+        let Self {
+            name,
+            return_type,
+            args,
+            self_reference,
+            cfg,
+            attributes,
+            ..
+        } = self;
+        let cfg = cfg.concat();
+        let attributes = attributes.concat();
+        let mut fn_args = match self_reference {
+            SelfReference::Mutable => vec!["&mut self".to_string()],
+            SelfReference::Immutable => vec!["&self".to_string()],
+            SelfReference::None | SelfReference::Owned => Vec::new(),
+        };
+        fn_args.extend(
+            args.iter()
+                .map(|Argument { name, ty, .. }| format!("{name}: {}", ty.format(None))),
+        );
+        let fn_args = fn_args.join(", ");
+        let return_type = match return_type {
+            Some(ty) => format!("-> {}", ty.format(None)),
+            None => String::new(),
+        };
+
+        format!(
+            "
+            {cfg}
+            {attributes}
+            fn {name}({fn_args}){return_type};
+            "
+        )
+    }
+
+    /// Generates the code in a protocol's `Methods` trait that adds the method to a class
+    /// adopting the protocol, like [`Function::registration`] does for methods
+    /// implemented in the class itself. The function the runtime calls is generic over
+    /// the class, and over whether panics are thrown, so it's picked for the class'
+    /// `#[on_panic]` strategy here.
+    fn protocol_registration(&self, trait_name: &str) -> String {
+        let Self {
+            name,
+            return_type,
+            args,
+            self_reference,
+            selector,
+            cfg,
+            ..
+        } = self;
+        let selector = selector.as_ref().unwrap_or(name);
+        let cfg = cfg
+            .iter()
+            .filter(|cfg| cfg.starts_with("#[cfg("))
+            .map(String::as_str)
+            .collect::<String>();
+        let encoding = |ty: &Type| {
+            format!(
+                "<{} as objective_rust::ffi::Encode>::ENCODING",
+                ty.format(None)
+            )
+        };
+
+        let mut encodings = match return_type {
+            Some(ty) => encoding(ty),
+            None => r#""v""#.to_string(),
+        };
+        encodings += r#", "@:""#;
+        let mut ffi_args = String::new();
+        let mut call_args = Vec::new();
+        for Argument { name, ty, .. } in args {
+            encodings += &format!(", {}", encoding(ty));
+            if ty.is_bool() {
+                ffi_args += &format!(", {name}: objective_rust::ObjcBool");
+                call_args.push(format!("bool::from({name})"));
+            } else {
+                ffi_args += &format!(", {name}: {}", ty.format(None));
+                call_args.push(name.clone());
+            }
+        }
+
+        let (ffi_return, call) = match return_type {
+            Some(ty) if ty.is_bool() => (
+                "-> objective_rust::ObjcBool".to_string(),
+                "objective_rust::ObjcBool::from",
+            ),
+            Some(ty) => (format!("-> {}", ty.format(None)), ""),
+            None => (String::new(), ""),
+        };
+        let (receiver, setup, add_method) = match self_reference {
+            SelfReference::None => (
+                "_class: objective_rust::ffi::Class".to_string(),
+                String::new(),
+                "add_class_method",
+            ),
+            _ => {
+                let (this, this_ref) = match self_reference {
+                    SelfReference::Mutable => ("mut this", "&mut this"),
+                    _ => ("this", "&this"),
+                };
+                call_args.insert(0, this_ref.to_string());
+                (
+                    "this: *mut <T as objective_rust::ffi::BoundClass>::Instance".to_string(),
+                    // The receiver is only borrowed, so it mustn't be released.
+                    format!(
+                        "
+                        let {this} = core::mem::ManuallyDrop::new(unsafe {{
+                            T::from_raw(core::ptr::NonNull::new(this).unwrap())
+                        }});
+                        "
+                    ),
+                    "add_method",
+                )
+            }
+        };
+        let call_args = call_args.join(", ");
+
+        format!(
+            r#"
+            {cfg}
+            {{
+                extern "C-unwind" fn {name}<T: {trait_name}, const THROW: bool>(
+                    {receiver},
+                    _cmd: objective_rust::ffi::Selector
+                    {ffi_args}
+                ) {ffi_return} {{
+                    let strategy = match THROW {{
+                        true => objective_rust::ffi::PanicStrategy::Throw,
+                        false => objective_rust::ffi::PanicStrategy::Abort,
+                    }};
+                    objective_rust::ffi::call_rust_method(
+                        strategy,
+                        core::any::type_name::<T>(),
+                        "{selector}",
+                        move || {{
+                            {setup}
+                            {call}(<T as {trait_name}>::{name}({call_args}))
+                        }},
+                    )
+                }}
+                let imp = match strategy {{
+                    objective_rust::ffi::PanicStrategy::Abort => {name}::<Self, false> as *const (),
+                    objective_rust::ffi::PanicStrategy::Throw => {name}::<Self, true> as *const (),
+                }};
+                let added = unsafe {{
+                    builder.{add_method}(
+                        objective_rust::ffi::get_selector("{selector}").unwrap(),
+                        imp,
+                        &[{encodings}].concat(),
+                    )
+                }};
+                assert!(
+                    added,
+                    "`{{}}` implements `{selector}` more than once",
+                    core::any::type_name::<Self>(),
+                );
+            }}
+            "#
+        )
+    }
+
+    /// Generates `super_<name>`, which calls the superclass' implementation of a method
+    /// implemented in Rust with `objc_msgSendSuper`, for overrides that extend it (like
+    /// `init` and `dealloc`, which have to call `super`'s).
+    fn super_call(&self, instance_name: &str, superclass: &str) -> String {
+        let Self {
+            name,
+            return_type,
+            args,
+            self_reference,
+            selector,
+            cfg,
+            ..
+        } = self;
+        let selector = selector.as_ref().unwrap_or(name);
+        let cfg = cfg.concat();
+
+        let mut rust_args = String::new();
+        let mut ffi_args = String::new();
+        let mut call_args = String::new();
+        for Argument { name, ty, .. } in args {
+            rust_args += &format!(", {name}: {}", ty.format(Some(instance_name)));
+            if ty.is_bool() {
+                ffi_args += ", objective_rust::ObjcBool";
+                call_args += &format!(", objective_rust::ObjcBool::from({name})");
+            } else {
+                ffi_args += &format!(", {}", ty.format(Some(instance_name)));
+                call_args += &format!(", {name}");
+            }
+        }
+        let rust_args = match self_reference {
+            SelfReference::None => rust_args.strip_prefix(", ").unwrap_or_default(),
+            _ => &rust_args,
+        };
+
+        let (return_type, ffi_return, ret, convert) = match return_type {
+            Some(ty) if ty.is_bool() => (
+                "-> bool".to_string(),
+                "-> objective_rust::ObjcBool".to_string(),
+                "objective_rust::ObjcBool".to_string(),
+                "bool::from",
+            ),
+            Some(ty) => {
+                let ty = ty.format(Some(instance_name));
+                (format!("-> {ty}"), format!("-> {ty}"), ty, "")
+            }
+            None => (String::new(), String::new(), "()".to_string(), ""),
+        };
+        // Class methods are sent to the class, and call the superclass' class method.
+        let (receiver, super_class) = match self_reference {
+            SelfReference::None => (
+                "Self::get_objc_class().as_ptr()",
+                format!(r#"objective_rust::ffi::get_metaclass("{superclass}").unwrap()"#),
+            ),
+            _ => (
+                "self.0.as_ptr().cast()",
+                format!(r#"objective_rust::ffi::get_class("{superclass}").unwrap()"#),
+            ),
+        };
+
+        format!(
+            r#"
+            {cfg}
+            /// Calls the superclass' implementation of `{selector}`.
+            pub fn super_{name}({self_reference}{rust_args}) {return_type} {{
+                let receiver = objective_rust::ffi::ObjcSuper {{
+                    receiver: {receiver},
+                    super_class: {super_class},
+                }};
+                let sel = objective_rust::ffi::get_selector("{selector}").unwrap();
+                let func: extern "C" fn(
+                    *const objective_rust::ffi::ObjcSuper,
+                    objective_rust::ffi::Selector
+                    {ffi_args}
+                ) {ffi_return} = unsafe {{
+                    core::mem::transmute(objective_rust::ffi::get_msg_send_super_for::<{ret}>())
+                }};
+
+                {convert}(func(&receiver, sel{call_args}))
+            }}
+            "#
+        )
+    }
+
+    /// If the method returns a borrowed `NSString`, as an [`NSStr`](objective_rust::ffi::NSStr).
+    pub fn returns_borrowed_string(&self) -> bool {
+        matches!(
+            &self.return_type,
+            Some(Type::Absolute(ty, _)) if ty == "NSStr" || ty.ends_with("::NSStr")
+        )
+    }
+}
+
+impl Display for SelfReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Self::None => "",
+            Self::Owned => "self",
+            Self::Immutable => "&self",
+            Self::Mutable => "&mut self",
+        };
+        write!(f, "{text}")
+    }
+}
+impl Type {
+    /// Picks what `Self` means in this type: the Rust wrapper when it's used by value,
+    /// or the Objective-C instance when it's behind a pointer (or `Id`). Slices,
+    /// borrows, and `Option`s go by the type they contain.
+    fn self_ty<'a>(&self, wrapper: &'a str, instance: &'a str) -> &'a str {
+        match self {
+            Self::Absolute(_, _) => wrapper,
+            Self::Slice(ty, _) | Self::Borrow(_, ty, _) | Self::Option(ty, _) => {
+                ty.self_ty(wrapper, instance)
+            }
+            Self::Pointer(_, _, _) | Self::Id(_, _, _) | Self::Tuple(_, _) | Self::Block(_, _) => {
+                instance
+            }
+        }
+    }
+
+    /// The type's Objective-C type encoding, for checking methods' signatures. Types
+    /// objective-rust doesn't know the encoding of (like structs) are `?`, which
+    /// matches any type.
+    fn encoding(&self) -> &'static str {
+        match self {
+            Self::Pointer(_, _, _)
+            | Self::Borrow(_, _, _)
+            | Self::Option(_, _)
+            | Self::Id(_, _, _) => "^v",
+            Self::Block(_, _) => "@?",
+            Self::Tuple(types, _) if types.is_empty() => "v",
+            Self::Absolute(ty, _) => match ty.as_str() {
+                // `ffi`'s aliases. Every target objective-rust supports is 64-bit.
+                _ if ty == "NSInteger" || ty.ends_with("::NSInteger") => "q",
+                _ if ty == "NSUInteger" || ty.ends_with("::NSUInteger") => "Q",
+                _ if ty == "CGFloat" || ty.ends_with("::CGFloat") => "d",
+                "Self" => "@",
+                "bool" => "B",
+                "i8" => "c",
+                "u8" => "C",
+                "i16" => "s",
+                "u16" => "S",
+                "i32" => "i",
+                "u32" => "I",
+                "i64" | "isize" => "q",
+                "u64" | "usize" => "Q",
+                "i128" => "t",
+                "u128" => "T",
+                "f32" => "f",
+                "f64" => "d",
+                _ if self.is_any_bool() => "c",
+                _ => "?",
+            },
+            Self::Tuple(_, _) | Self::Slice(_, _) => "?",
+        }
+    }
+
+    /// If this is Rust's `bool`, which is converted to and from Objective-C's `BOOL`.
+    fn is_bool(&self) -> bool {
+        matches!(self, Self::Absolute(ty, _) if ty == "bool")
+    }
+
+    /// Formats the type. If `self_ty` is set, any `Self` in the type is replaced with it.
+    fn format(&self, self_ty: Option<&str>) -> String {
+        match self {
+            Self::Absolute(ty, _) => match self_ty {
+                Some(self_ty) if ty == "Self" => self_ty.to_string(),
+                _ => ty.clone(),
+            },
+            Self::Borrow(mutability, ty, _) => match mutability {
+                Mutability::Immut => format!("&{}", ty.format(self_ty)),
+                Mutability::Mut => format!("&mut {}", ty.format(self_ty)),
+            },
+            Self::Pointer(mutability, ty, _) => match mutability {
+                Mutability::Immut => format!("*const {}", ty.format(self_ty)),
+                Mutability::Mut => format!("*mut {}", ty.format(self_ty)),
+            },
+            Self::Slice(ty, _) => format!("&[{}]", ty.format(self_ty)),
+            Self::Option(ty, _) => format!("Option<{}>", ty.format(self_ty)),
+            Self::Id(path, ty, _) => format!("{path}<{}>", ty.format(self_ty)),
+            Self::Block(ty, _) => ty.clone(),
+            Self::Tuple(types, _) => {
+                let mut text = "(".to_string();
+                for ty in types {
+                    text += &ty.format(self_ty);
+                    text += ","
+                }
+                text += ")";
+
+                text
+            }
+        }
+    }
+}
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(None))
+    }
+}
+
+// Tokens (and so `Type`s) can only be made while a proc macro is running, so these
+// only cover items that don't need any.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collapses runs of whitespace into a single space, so the generated code can be
+    /// matched without caring about its indentation.
+    fn squash(code: impl Display) -> String {
+        code.to_string()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// A method that takes no arguments and returns nothing.
+    fn method(name: &str, self_reference: SelfReference) -> Function {
+        Function {
+            name: name.into(),
+            return_type: None,
+            args: Vec::new(),
+            self_reference,
+            selector: None,
+            cfg: Vec::new(),
+            cf_return: None,
+            expose_cmd: false,
+            handle_return: None,
+            returns_as: None,
+            bool_is_success: false,
+            borrowed_return: false,
+            nullable: false,
+            out_struct: false,
+            throws: false,
+            error_param: false,
+            nil_is_err: false,
+            variadic: false,
+            out_params: false,
+            constructor: false,
+            singleton: false,
+            needs_pool: false,
+            cached: false,
+            body: None,
+            attributes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn static_stub_panics() {
+        let item = squash(Static {
+            name: "NSWindowDidResizeNotification".into(),
+            ty: "*const NSStringInstance".into(),
+            cfg: Vec::new(),
+            attributes: Vec::new(),
+        });
+
+        assert!(
+            item.contains("pub fn NSWindowDidResizeNotification() -> *const NSStringInstance {")
+        );
+        assert!(item.contains("static NSWindowDidResizeNotification: *const NSStringInstance;"));
+        assert!(item.contains(
+            r#"#[cfg(not(target_os = "macos"))] panic!("objective-rust only supports macOS")"#
+        ));
+        assert!(!item.contains("unimplemented!"));
+    }
+
+    #[test]
+    fn empty_protocol_is_implemented_for_every_class() {
+        let protocol = squash(Protocol {
+            name: "NSCopying".into(),
+            cfg: Vec::new(),
+            attributes: Vec::new(),
+            methods: Vec::new(),
+        });
+
+        assert!(protocol.contains("pub trait NSCopyingMethods: objective_rust::ffi::BoundClass {"));
+        assert!(
+            protocol.contains("impl<T: objective_rust::ffi::BoundClass> NSCopyingMethods for T {}")
+        );
+        assert!(protocol.contains(r#"objective_rust::ffi::get_protocol("NSCopying")"#));
+    }
+
+    #[test]
+    fn protocol_methods_become_a_trait() {
+        let protocol = squash(Protocol {
+            name: "NSApplicationDelegate".into(),
+            cfg: Vec::new(),
+            attributes: Vec::new(),
+            methods: vec![method("applicationWillTerminate", SelfReference::Immutable)],
+        });
+
+        assert!(protocol.contains("fn applicationWillTerminate(&self);"));
+        assert!(protocol.contains(r#"extern "C-unwind" fn"#));
+        assert!(
+            protocol.contains(r#"objective_rust::ffi::get_selector("applicationWillTerminate")"#)
+        );
+        // Classes implement the trait themselves.
+        assert!(!protocol.contains("NSApplicationDelegateMethods for T"));
+    }
+
+    #[test]
+    fn void_method_calls_through_the_vtable() {
+        let mut class = Class::new("NSWindow".into());
+        class
+            .methods
+            .push(method("display", SelfReference::Immutable));
+        let class = squash(class);
+
+        assert!(class.contains("pub fn display(&self)"));
+        assert!(class.contains("func(self.0.as_ptr(), sel)"));
+        // Methods without a return value are encoded as returning `void`, after the
+        // receiver and selector.
+        assert!(class.contains(r#"check_method_encoding(class, sel, "v@:")"#));
+        assert!(class.contains(r#"get_selector("display")"#));
+    }
+
+    #[test]
+    fn explicit_selector_is_used() {
+        let mut class = Class::new("NSWindow".into());
+        let mut center = method("center_window", SelfReference::Immutable);
+        center.selector = Some("center".into());
+        class.methods.push(center);
+        let class = squash(class);
+
+        assert!(class.contains("pub fn center_window(&self)"));
+        assert!(class.contains(r#"get_selector("center")"#));
+        assert!(!class.contains(r#"get_selector("center_window")"#));
+    }
+
+    #[test]
+    fn class_methods_are_checked_on_the_metaclass() {
+        let mut class = Class::new("NSApplication".into());
+        class.methods.push(method("load", SelfReference::None));
+        let class = squash(class);
+
+        assert!(class.contains("pub fn load()"));
+        assert!(class.contains(r#"check_method_encoding(metaclass, sel, "v@:")"#));
+    }
+
+    #[test]
+    fn try_load_checks_before_loading() {
+        let mut class = Class::new("NSWindow".into());
+        class
+            .methods
+            .push(method("display", SelfReference::Immutable));
+        let class = squash(class);
+
+        let try_load = &class[class.find("pub fn try_load()").unwrap()..];
+        let checks = try_load
+            .find("method_encoding_matches(class, sel, \"v@:\")")
+            .unwrap();
+        assert!(checks < try_load.find("Self::preload();").unwrap());
+        assert!(try_load
+            .contains("LoadError::Encoding { class: \"NSWindow\", selector: \"display\", }"));
+    }
+
+    #[test]
+    fn needs_pool_asserts_in_debug_builds() {
+        let mut class = Class::new("NSString".into());
+        let mut string = method("string", SelfReference::None);
+        string.needs_pool = true;
+        class.methods.push(string);
+        let class = squash(class);
+
+        assert!(class.contains("#[cfg(debug_assertions)] assert!("));
+        assert!(class.contains("objective_rust::ffi::autorelease_pool_active()"));
+    }
+
+    #[test]
+    fn thread_safe_implements_send_and_sync() {
+        let mut class = Class::new("NSString".into());
+        assert!(!squash(&class).contains("unsafe impl Send for NSString"));
+
+        class.thread_safe = true;
+        let class = squash(class);
+        assert!(class.contains("unsafe impl Send for NSString {}"));
+        assert!(class.contains("unsafe impl Sync for NSString {}"));
+    }
+
+    #[test]
+    fn instance_type_renames_the_instance() {
+        let mut class = Class::new("NSWindow".into());
+        assert_eq!(class.instance_name(), "NSWindowInstance");
+
+        class.instance_type = Some("RawNSWindow".into());
+        assert_eq!(class.instance_name(), "RawNSWindow");
+        let class = squash(class);
+        assert!(class.contains("pub struct RawNSWindow"));
+        assert!(!class.contains("NSWindowInstance"));
+    }
+
+    #[test]
+    fn ivars_have_accessors() {
+        let mut class = Class::new("StateHolder".into());
+        class.superclass = Some("NSObject".into());
+        class.ivars.push(("_state".into(), "*mut c_void".into()));
+        let class = squash(class);
+
+        // The leading `_` is dropped from the accessors' names.
+        assert!(class.contains("pub fn state(&self) -> *mut c_void"));
+        assert!(class.contains("pub fn set_state(&mut self, value: *mut c_void)"));
+        assert!(class.contains(r#"add_ivar::<*mut c_void>("_state")"#));
+        // Ivars that aren't pointer-sized don't compile.
+        assert!(class.contains(
+            "const _: () = assert!( core::mem::size_of::<*mut c_void>() == core::mem::size_of::<*mut ()>(),"
+        ));
+    }
+
+    #[test]
+    fn adopted_protocols_register_their_methods() {
+        let mut class = Class::new("AppDelegate".into());
+        class.superclass = Some("NSObject".into());
+        class.protocols.push("NSApplicationDelegate".into());
+        class.panic_strategy = PanicStrategy::Throw;
+        let class = squash(class);
+
+        assert!(class.contains(r#"objective_rust::ffi::get_protocol("NSApplicationDelegate")"#));
+        assert!(class.contains(
+            "<AppDelegate as NSApplicationDelegateMethods>::register_methods( &mut builder, objective_rust::ffi::PanicStrategy::Throw, );"
+        ));
+    }
+}
@@ -19,11 +19,15 @@ pub enum ErrorKind {
     UnnamedClass,
     /// There was no `;` after a class name.
     NoSemicolonAfterClass,
+    /// A `static` wasn't written as `static NAME: Type;`.
+    BadStatic,
+    /// There was no superclass name after a `:` in a class definition.
+    NoSuperclass,
     /// A class was defined twice. Stores the class name.
     ClassDefinedTwice(String),
     /// A type was expected but not found.
     NoType,
-    /// &T/&mut T are currently unsupported
+    /// &T/&mut T (or a block) was used somewhere other than a method argument.
     BorrowsUnsupported,
     /// An error while parsing a method.
     Method(MethodError),
@@ -33,6 +37,10 @@ pub enum ErrorKind {
     GiveUp,
     /// Expected a comma between types
     NoComma,
+    /// An argument to the `objrs` macro itself that it doesn't support.
+    UnknownMacroArgument,
+    /// Something other than a class, method, or attribute in an `extern "objc"` block.
+    UnexpectedInBindingBlock,
 }
 impl Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -44,13 +52,25 @@ impl Display for ErrorKind {
             Self::MethodBeforeClass => "A class needs to be defined before methods can be defined.".into(),
             Self::UnnamedClass => "Expected a class name after `type`.".into(),
             Self::NoSemicolonAfterClass => "Expected a `;` beside the class name.".into(),
+            Self::BadStatic => {
+                "Expected `static NAME: Type;`. Mutable statics aren't supported.".into()
+            }
+            Self::NoSuperclass => "Expected the name of the class' superclass after `:`.".into(),
             Self::ClassDefinedTwice(name) => format!("Class {name} is defined multiple times."),
             Self::NoType => "Expected a type here.".into(),
-            Self::BorrowsUnsupported => "Borrows are currently unsupported in Objective-Rust for safety reasons.".into(),
+            Self::BorrowsUnsupported => {
+                "Borrows and blocks can only be used as method arguments.".into()
+            }
             Self::Method(method) => method.to_string(),
             Self::Attribute(err) => err.to_string(),
             Self::GiveUp => "Unknown syntax".into(),
             Self::NoComma => "Expected a comma between types".into(),
+            Self::UnexpectedInBindingBlock => {
+                "Expected a class (`type`), method (`fn`), static, or attribute in an `extern \"objc\"` block.".into()
+            }
+            Self::UnknownMacroArgument => {
+                "Unknown `objrs` argument; expected `framework = \"Name\"` or `enums(...)`.".into()
+            }
         };
         write!(f, "{err}")
     }
@@ -74,6 +94,23 @@ pub enum MethodError {
     NoArgumentComma,
     /// Found an `&`, but no `self` or `mut self` after it, in method arguments.
     ExpectedSelfReference,
+    /// A method returned a borrow, but doesn't borrow `self` for it to borrow from.
+    BorrowWithoutSelf,
+    /// A method has a body, but its class doesn't have a superclass, so it's bound
+    /// instead of defined from Rust.
+    BodyWithoutSuperclass,
+    /// A protocol's method has a body. They're implemented by the classes that adopt
+    /// the protocol.
+    BodyInProtocol,
+    /// A method implemented in Rust uses a type or attribute that's only supported for
+    /// bound methods.
+    UnsupportedInImplementation,
+    /// A `#[out_params]` method's selector doesn't take one argument for each of the
+    /// method's arguments and out-params.
+    OutParamsArity,
+    /// A method's selector has a different number of `:`s than the arguments the
+    /// method passes to Objective-C.
+    SelectorArity { colons: usize, args: usize },
 }
 impl Display for MethodError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -88,6 +125,27 @@ impl Display for MethodError {
             Self::NoArgumentColon => "Expected a `:` after the argument's name.",
             Self::NoArgumentComma => "Expected a `,` in between arguments.",
             Self::ExpectedSelfReference => "Expected `self` or `mut self` after the `&`.",
+            Self::BorrowWithoutSelf => {
+                "Methods that return a borrow must take `&self` or `&mut self`."
+            }
+            Self::BodyInProtocol => {
+                "Protocol methods can't have a body; implement them with the protocol's `Methods` trait on a class that adopts it."
+            }
+            Self::BodyWithoutSuperclass => {
+                "Only classes defined from Rust, with a superclass (like `type MyClass: NSObject;`), can implement methods."
+            }
+            Self::OutParamsArity => {
+                "The selector needs a `:` for each argument, plus one for each value in the returned tuple."
+            }
+            Self::SelectorArity { colons, args } => {
+                return write!(
+                    f,
+                    "The selector has {colons} `:`s, but the method passes {args} arguments to Objective-C. It needs one `:` per argument; slices count as two (their pointer and length)."
+                )
+            }
+            Self::UnsupportedInImplementation => {
+                "Methods implemented in Rust must take `&self`, `&mut self`, or no `self`; can only take and return primitives and pointers; and only support the `selector` and `cfg` attributes."
+            }
         };
         write!(f, "{err}")
     }
@@ -102,11 +160,22 @@ pub enum AttributeError {
     Unknown,
     /// No `=` was found after the attribute name.
     NoEquals,
-    /// No value was found after a `=` in an attribute assignment.
+    /// No value was found after a `=` or in the `()` of an attribute.
     NoValue,
     /// An unexpected type was used for the attribute's value.
     /// Stores the expected type.
     Type(String),
+    /// The attribute's value isn't one it supports.
+    /// Stores the values it does support.
+    UnknownValue(&'static str),
+    /// The attribute was used on something it doesn't support.
+    /// Stores what the attribute can be used on.
+    WrongTarget(&'static str),
+    /// The attribute was used more than once on the same class or method.
+    Duplicate,
+    /// The attribute can't be combined with another attribute on the same item.
+    /// Stores the other attribute's name.
+    Conflict(&'static str),
 }
 impl Display for AttributeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -115,8 +184,14 @@ impl Display for AttributeError {
             Self::NoName => "Expected an attribute name after `[`.".into(),
             Self::Unknown => "Unknown attribute.".into(),
             Self::NoEquals => "Expected `=` after the attribute name.".into(),
-            Self::NoValue => "Expected a value after the `=`.".into(),
+            Self::NoValue => "Expected a value for the attribute.".into(),
             Self::Type(expected) => format!("Expected a `{expected}` literal."),
+            Self::UnknownValue(expected) => format!("Unknown value; expected {expected}."),
+            Self::WrongTarget(target) => format!("This attribute can only be used on {target}."),
+            Self::Conflict(other) => format!("This attribute can't be used with `#[{other}]`."),
+            Self::Duplicate => {
+                "This attribute was already applied; it can only be used once per item.".into()
+            }
         };
         write!(f, "{err}")
     }
@@ -0,0 +1,394 @@
+//! The parser behind objective-rust's `objrs` macro.
+//!
+//! This crate exposes the types `objrs` parses `extern "objc"` blocks into, so other
+//! tools (like binding generators) can analyse bindings with the same grammar as the
+//! macro. [`parser::parse_macro_input`] parses a token stream into [`Class`]es and
+//! the tokens it passed through unchanged.
+//!
+//! # Stability
+//! This is objective-rust's internals, not a stable API: types, fields, and error
+//! variants change whenever the macro gains a feature, and this crate's version only
+//! tracks objective-rust's. Pin an exact version if you depend on it.
+//!
+//! The parser works on [`proc_macro`] tokens, which can only be created and inspected
+//! while a procedural macro is running. That means it can be used from other proc
+//! macros (or code called by them), but not from regular binaries or build scripts.
+
+extern crate proc_macro;
+
+#[doc(hidden)]
+pub mod codegen;
+pub mod error;
+pub mod parser;
+
+use {
+    error::*,
+    proc_macro::{Group, Span},
+};
+
+/// Options given to the macro itself, like `#[objrs(framework = "AppKit")]`.
+#[derive(Default)]
+pub struct MacroConfig {
+    /// Frameworks to link to, set with `framework = "Name"`.
+    pub frameworks: Vec<String>,
+    /// Types to check are FFI-safe enums, set with `enums(Name, ...)`.
+    pub enums: Vec<String>,
+}
+
+/// A protocol declared with `#[protocol] type Name;`.
+pub struct Protocol {
+    pub name: String,
+    /// `cfg`/`cfg_attr` attributes to emit on every item generated for the protocol.
+    pub cfg: Vec<String>,
+    /// Other attributes, which are emitted on the generated struct.
+    pub attributes: Vec<String>,
+    /// The protocol's required methods, which classes that adopt it implement with its
+    /// generated `{name}Methods` trait.
+    pub methods: Vec<Function>,
+}
+
+/// A global constant exported by a framework, declared with `static NAME: Type;`.
+pub struct Static {
+    pub name: String,
+    /// The static's type, as it was written.
+    pub ty: String,
+    /// `cfg`/`cfg_attr` attributes to emit on the generated accessor.
+    pub cfg: Vec<String>,
+    /// Other attributes, which are emitted on the generated accessor.
+    pub attributes: Vec<String>,
+}
+
+pub struct Class {
+    pub name: String,
+    pub methods: Vec<Function>,
+    /// A function to call with the instance before it's released.
+    pub on_drop: Option<String>,
+    /// If `description` and `debug_description` methods should be generated.
+    pub description: bool,
+    /// Where the class' vtable is stored.
+    pub vtable_storage: VTableStorage,
+    /// `cfg`/`cfg_attr` attributes to emit on every item generated for the class.
+    pub cfg: Vec<String>,
+    /// The name of the class' opaque instance type, set with `#[instance_type]`.
+    pub instance_type: Option<String>,
+    /// Other attributes, which are emitted on the generated wrapper struct.
+    pub attributes: Vec<String>,
+    /// If `Ord` and friends should be implemented with `compare:`, set with
+    /// `#[comparable]`.
+    pub comparable: bool,
+    /// If `PartialEq` and `Eq` should be implemented with `isEqual:`, set with
+    /// `#[derive_objc(Eq)]`.
+    pub derive_eq: bool,
+    /// If `Hash` should be implemented with `hash`, set with `#[derive_objc(Hash)]`.
+    pub derive_hash: bool,
+    /// The class this one inherits from, if it's defined from Rust instead of bound
+    /// (`type MyClass: NSObject;`).
+    pub superclass: Option<String>,
+    /// Instance variables to generate accessors for (and to add to the class, if it's
+    /// defined from Rust), as `(name, type)`. Set with `#[ivar(name: Type)]`.
+    pub ivars: Vec<(String, String)>,
+    /// Protocols a class defined from Rust adopts, set with `#[protocols(...)]`.
+    pub protocols: Vec<String>,
+    /// If the wrapper struct should implement `Send` and `Sync`, set with
+    /// `#[thread_safe]`.
+    pub thread_safe: bool,
+    /// What the class' methods implemented in Rust do when they panic, set with
+    /// `#[on_panic(...)]`.
+    pub panic_strategy: PanicStrategy,
+}
+impl Class {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            methods: Vec::new(),
+            on_drop: None,
+            description: false,
+            vtable_storage: VTableStorage::Global,
+            cfg: Vec::new(),
+            instance_type: None,
+            attributes: Vec::new(),
+            comparable: false,
+            derive_eq: false,
+            derive_hash: false,
+            superclass: None,
+            ivars: Vec::new(),
+            protocols: Vec::new(),
+            thread_safe: false,
+            panic_strategy: PanicStrategy::Abort,
+        }
+    }
+
+    /// The name of the opaque type for instances of the class. Defaults to
+    /// `{class name}Instance`.
+    pub fn instance_name(&self) -> String {
+        match &self.instance_type {
+            Some(name) => name.clone(),
+            None => format!("{}Instance", self.name),
+        }
+    }
+}
+pub struct Function {
+    pub name: String,
+    pub return_type: Option<Type>,
+    pub args: Vec<Argument>,
+    pub self_reference: SelfReference,
+    /// The selector set with `#[selector]`. If this isn't set, the method's name is used.
+    pub selector: Option<String>,
+    /// `cfg`/`cfg_attr` attributes to emit on the generated method.
+    pub cfg: Vec<String>,
+    /// Set if the method returns a toll-free bridged CoreFoundation type (`#[cf]`).
+    pub cf_return: Option<CfReturn>,
+    /// If the caller passes the method's selector (`_cmd`), set with `#[expose_cmd]`.
+    pub expose_cmd: bool,
+    /// A newtype to wrap a returned opaque pointer in, set with `#[returns_handle]`.
+    pub handle_return: Option<String>,
+    /// A type to convert the return value into with `From`, set with `#[returns_as]`.
+    pub returns_as: Option<String>,
+    /// If a `bool` return is mapped to `Result<(), ()>`, set with `#[bool_is_success]`.
+    pub bool_is_success: bool,
+    /// If the method returns a borrowed instance of a bound class, set with `#[borrowed]`.
+    pub borrowed_return: bool,
+    /// If a returned pointer is wrapped in `Option<NonNull<T>>`, set with `#[nullable]`.
+    pub nullable: bool,
+    /// If the return value is filled in through a pointer passed as the last
+    /// argument, set with `#[out_struct]`.
+    pub out_struct: bool,
+    /// If Objective-C exceptions are caught and returned as `Err`, set with `#[throws]`.
+    pub throws: bool,
+    /// If the last argument is an `NSError **` out-parameter, set with `#[error_param]`.
+    /// It stays in `args`, but isn't part of the generated method's signature.
+    pub error_param: bool,
+    /// If a returned `None` (nil) is mapped to `Err(())`, set with `#[nil_is_err]`.
+    pub nil_is_err: bool,
+    /// If the method's last argument (a slice) is passed as nil-terminated variadic
+    /// arguments, set with `#[variadic]`.
+    pub variadic: bool,
+    /// If the method returns a tuple that it fills through trailing pointer arguments,
+    /// set with `#[out_params]`.
+    pub out_params: bool,
+    /// If the method is an initialiser that's called on a newly allocated instance,
+    /// set with `#[constructor]`. It's declared without `self`.
+    pub constructor: bool,
+    /// If the class method returns a shared instance that's never released, set with
+    /// `#[singleton]`.
+    pub singleton: bool,
+    /// If calling the method without an `AutoreleasePool` should panic in debug
+    /// builds, set with `#[needs_pool]`.
+    pub needs_pool: bool,
+    /// If the returned `NSStr` is copied into a `String` once, and cached on the
+    /// instance, set with `#[cached]`.
+    pub cached: bool,
+    /// The method's body, if it's implemented in Rust (in a class with a superclass)
+    /// instead of bound.
+    pub body: Option<Group>,
+    /// Other attributes, which are emitted on the generated method.
+    pub attributes: Vec<String>,
+}
+pub struct Argument {
+    pub name: String,
+    pub ty: Type,
+    /// If the method takes ownership of this argument (`#[consumed]`).
+    pub consumed: bool,
+    /// If the argument is taken by reference, and passed to Objective-C as a copy of
+    /// the value (with the value's own type) instead of a pointer to it. Property
+    /// setters take objects this way, so they aren't moved into the call and leaked.
+    pub by_ref: bool,
+}
+#[derive(Clone)]
+pub enum Type {
+    Pointer(Mutability, Box<Self>, Span),
+    /// `&T`/`&mut T`. Only supported for arguments, which are passed to Objective-C
+    /// as a pointer.
+    Borrow(Mutability, Box<Self>, Span),
+    Absolute(String, Span),
+    Tuple(Vec<Self>, Span),
+    /// `&[T]`. Passed to Objective-C as a pointer and a length.
+    Slice(Box<Self>, Span),
+    /// `Option<T>`. Only FFI-safe for non-nullable types, like bound classes, where
+    /// `None` is nil.
+    Option(Box<Self>, Span),
+    /// `objective_rust::ffi::Id<T>`, an owned reference to an object. Stores the path
+    /// `Id` was written with.
+    Id(String, Box<Self>, Span),
+    /// `objective_rust::ffi::Block<dyn Fn(...) -> R>`, a closure passed as a block.
+    /// Only supported for arguments. Stores the whole type, as it was written.
+    Block(String, Span),
+}
+impl Type {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Pointer(_, _, span) => *span,
+            Self::Borrow(_, _, span) => *span,
+            Self::Absolute(_, span) => *span,
+            Self::Tuple(_, span) => *span,
+            Self::Slice(_, span) => *span,
+            Self::Option(_, span) => *span,
+            Self::Id(_, _, span) => *span,
+            Self::Block(_, span) => *span,
+        }
+    }
+
+    /// If this is Rust's `bool` or `ObjcBool`.
+    pub fn is_any_bool(&self) -> bool {
+        matches!(
+            self,
+            Self::Absolute(ty, _) if ty == "bool" || ty == "ObjcBool" || ty.ends_with("::ObjcBool")
+        )
+    }
+
+    /// If this is a number, `bool`, or pointer, which don't own anything.
+    pub fn is_primitive(&self) -> bool {
+        match self {
+            Self::Pointer(_, _, _) => true,
+            Self::Absolute(ty, _) => matches!(
+                ty.rsplit("::").next().unwrap(),
+                "bool"
+                    | "ObjcBool"
+                    | "i8"
+                    | "u8"
+                    | "i16"
+                    | "u16"
+                    | "i32"
+                    | "u32"
+                    | "i64"
+                    | "u64"
+                    | "i128"
+                    | "u128"
+                    | "isize"
+                    | "usize"
+                    | "f32"
+                    | "f64"
+                    | "NSInteger"
+                    | "NSUInteger"
+                    | "CGFloat"
+            ),
+            _ => false,
+        }
+    }
+
+    /// If this is `Self` or `Option<Self>`.
+    pub fn is_self_or_optional_self(&self) -> bool {
+        match self {
+            Self::Absolute(ty, _) => ty == "Self",
+            Self::Option(ty, _) => matches!(&**ty, Self::Absolute(ty, _) if ty == "Self"),
+            _ => false,
+        }
+    }
+}
+#[derive(Clone, Copy)]
+pub enum Mutability {
+    Mut,
+    Immut,
+}
+#[derive(PartialEq)]
+pub enum SelfReference {
+    /// Static/class method
+    None,
+    /// &self
+    Immutable,
+    /// &mut self
+    Mutable,
+    /// self
+    Owned,
+}
+pub enum Attribute {
+    /// Sets the name objective-rust will use to find a method's selector.
+    Selector(String),
+    /// Sets a function that's called with a class' instance before it's released.
+    OnDrop(String),
+    /// Generates `description` and `debug_description` methods for a class.
+    Description,
+    /// Implements `PartialEq`, `Eq`, `PartialOrd`, and `Ord` for a class with its
+    /// `compare:` method.
+    Comparable,
+    /// Implements Rust traits for a class with its Objective-C methods.
+    DeriveObjc(Vec<ObjcDerive>),
+    /// Sets where a class' vtable is stored.
+    VTable(VTableStorage),
+    /// A `cfg` or `cfg_attr` attribute, which is copied onto the generated code.
+    /// Stores the whole attribute, including the `#[]`.
+    Cfg(String),
+    /// Marks a method as returning a toll-free bridged CoreFoundation type.
+    Cf(CfReturn),
+    /// Renames a class' opaque instance type.
+    InstanceType(String),
+    /// Makes a method take the selector to call it with as an argument.
+    ExposeCmd,
+    /// Wraps a method's returned pointer in a newtype.
+    ReturnsHandle(String),
+    /// Converts a method's return value into another type.
+    ReturnsAs(String),
+    /// Returns `Err` from a method when it returns `NO`.
+    BoolIsSuccess,
+    /// Marks a method as returning an object it doesn't give the caller ownership of.
+    Borrowed,
+    /// Wraps a method's returned pointer in `Option<NonNull<T>>`.
+    Nullable,
+    /// Returns a struct that the method fills through a pointer argument.
+    OutStruct,
+    /// Catches Objective-C exceptions thrown by a method.
+    Throws,
+    /// Marks a method's last argument as an `NSError **` out-parameter.
+    ErrorParam,
+    /// Marks a class method as returning a shared instance the caller doesn't own.
+    Singleton,
+    /// Returns `Err` from a method when it returns nil.
+    NilIsErr,
+    /// Binds a setter for a property alongside its getter.
+    Property,
+    /// Passes a method's last argument, a slice, as nil-terminated variadic arguments.
+    Variadic,
+    /// Returns a tuple that the method fills through pointer arguments.
+    OutParams,
+    /// Makes an `init` method allocate the instance it's called on.
+    Constructor,
+    /// Asserts an autorelease pool is active when a method is called.
+    NeedsPool,
+    /// Caches the string a method returns on the instance.
+    Cached,
+    /// Binds one of a class' instance variables, with its name and type.
+    Ivar(String, String),
+    /// Protocols a class defined from Rust adopts.
+    Protocols(Vec<String>),
+    /// Declares a protocol instead of a class.
+    Protocol,
+    /// Implements `Send` and `Sync` for a class' wrapper struct.
+    ThreadSafe,
+    /// Sets what a class' methods implemented in Rust do when they panic.
+    OnPanic(PanicStrategy),
+    /// An attribute objective-rust doesn't handle itself, like `#[deprecated]`, which
+    /// is emitted on the generated code as is. Stores the whole attribute, including
+    /// the `#[]`.
+    Forward(String),
+}
+/// Traits `#[derive_objc]` can implement.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ObjcDerive {
+    /// `PartialEq` and `Eq`, with `isEqual:`.
+    Eq,
+    /// `Hash`, with `hash`.
+    Hash,
+}
+/// CoreFoundation's ownership rules for returned objects.
+#[derive(Clone, Copy)]
+pub enum CfReturn {
+    /// The caller owns the returned object (`Create`/`Copy` functions).
+    Create,
+    /// The caller doesn't own the returned object (`Get` functions).
+    Get,
+}
+#[derive(Clone, Copy, PartialEq)]
+pub enum VTableStorage {
+    /// Each thread loads and stores its own vtable, in a `thread_local!`.
+    ThreadLocal,
+    /// One vtable is loaded for the whole process, and stored in a `OnceLock`.
+    Global,
+}
+/// What a class' methods implemented in Rust do when they panic.
+#[derive(Clone, Copy, PartialEq)]
+pub enum PanicStrategy {
+    /// Abort the process.
+    Abort,
+    /// Raise an `NSException`.
+    Throw,
+}
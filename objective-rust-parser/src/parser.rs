@@ -0,0 +1,453 @@
+mod attribute;
+mod function;
+mod parse_type;
+
+pub use {attribute::parse_macro_args, parse_type::parse_type};
+
+use {
+    crate::{
+        Attribute, AttributeError, Class, Error, ErrorKind, MacroConfig, ObjcDerive, PanicStrategy,
+        Protocol, Static, VTableStorage,
+    },
+    function::MethodOwner,
+    proc_macro::{Delimiter, Group, TokenStream, TokenTree},
+    std::{collections::hash_map::HashMap, iter::Peekable},
+};
+
+pub enum ParserOutput {
+    Class(Class),
+    Protocol(Protocol),
+    Static(Static),
+    RawToken(TokenTree),
+}
+
+#[derive(Default)]
+struct ClassStore {
+    map: HashMap<String, Class>,
+}
+impl ClassStore {
+    pub fn insert(&mut self, class: Class) {
+        match self.map.get_mut(class.name.as_str()) {
+            Some(old_class) => {
+                old_class.methods.extend(class.methods);
+                if class.on_drop.is_some() {
+                    old_class.on_drop = class.on_drop;
+                }
+                old_class.description |= class.description;
+                old_class.comparable |= class.comparable;
+                old_class.derive_eq |= class.derive_eq;
+                old_class.derive_hash |= class.derive_hash;
+                old_class.ivars.extend(class.ivars);
+                old_class.protocols.extend(class.protocols);
+                old_class.thread_safe |= class.thread_safe;
+                if class.vtable_storage != VTableStorage::Global {
+                    old_class.vtable_storage = class.vtable_storage;
+                }
+                if class.panic_strategy != PanicStrategy::Abort {
+                    old_class.panic_strategy = class.panic_strategy;
+                }
+                if class.instance_type.is_some() {
+                    old_class.instance_type = class.instance_type;
+                }
+                old_class.attributes.extend(class.attributes);
+                if class.superclass.is_some() {
+                    old_class.superclass = class.superclass;
+                }
+                for cfg in class.cfg {
+                    if !old_class.cfg.contains(&cfg) {
+                        old_class.cfg.push(cfg);
+                    }
+                }
+            }
+            None => {
+                let _ = self.map.insert(class.name.clone(), class);
+            }
+        }
+    }
+
+    pub fn into_parser_output(self) -> impl Iterator<Item = ParserOutput> {
+        self.map.into_values().map(ParserOutput::Class)
+    }
+}
+
+pub fn parse_macro_input(
+    mut tokens: Peekable<impl Iterator<Item = TokenTree>>,
+) -> Result<Vec<ParserOutput>, Error> {
+    let mut classes = ClassStore::default();
+    let mut output = Vec::new();
+
+    while let Some(raw_token) = tokens.next() {
+        let token = raw_token.to_string();
+
+        if token == *"extern"
+            && tokens.peek().map(|token| token.to_string().to_lowercase())
+                == Some("\"objc\"".into())
+        {
+            let start_span = raw_token.span();
+            tokens.next().unwrap();
+
+            let Some(TokenTree::Group(group)) = tokens.next() else {
+                return Err(Error {
+                    start: start_span,
+                    end: start_span,
+                    kind: ErrorKind::UnknownObjcBinding,
+                });
+            };
+
+            if group.delimiter() != Delimiter::Brace {
+                return Err(Error {
+                    start: start_span,
+                    end: group.span(),
+                    kind: ErrorKind::BadBindingBrackets,
+                });
+            }
+
+            let (block_classes, items) = parse_extern_block(group.stream().into_iter().peekable())?;
+            block_classes.into_iter().for_each(|class| {
+                classes.insert(class);
+            });
+            output.extend(items);
+            continue;
+        }
+
+        if token == *"mod" {
+            if let Some(TokenTree::Ident(_)) = tokens.peek() {
+                let mod_name = tokens.next().unwrap();
+                let mod_name_span = mod_name.span();
+
+                let mut scope = vec![
+                    ParserOutput::RawToken(raw_token),
+                    ParserOutput::RawToken(mod_name),
+                ];
+
+                let Some(TokenTree::Group(braces)) = tokens.next() else {
+                    return Err(Error {
+                        start: mod_name_span,
+                        end: mod_name_span,
+                        kind: ErrorKind::GiveUp,
+                    });
+                };
+                if braces.delimiter() != Delimiter::Brace {
+                    return Err(Error {
+                        start: mod_name_span,
+                        end: mod_name_span,
+                        kind: ErrorKind::GiveUp,
+                    });
+                }
+
+                let scoped_output = parse_macro_input(braces.stream().into_iter().peekable())?;
+                // Frameworks are linked once, outside the module.
+                let scoped_tokens =
+                    crate::codegen::generate(scoped_output, &MacroConfig::default())?;
+                scope.push(ParserOutput::RawToken(TokenTree::Group(Group::new(
+                    Delimiter::Brace,
+                    scoped_tokens,
+                ))));
+                output.extend(scope);
+
+                continue;
+            }
+        }
+
+        output.push(ParserOutput::RawToken(raw_token));
+    }
+
+    output.extend(classes.into_parser_output());
+    Ok(output)
+}
+
+fn parse_extern_block(
+    mut tokens: Peekable<impl Iterator<Item = TokenTree>>,
+) -> Result<(Vec<Class>, Vec<ParserOutput>), Error> {
+    let mut classes = ClassStore::default();
+    let mut items = Vec::new();
+    let mut current_class = None;
+    let mut current_protocol = None;
+    let mut active_attributes = Vec::new();
+
+    while let Some(raw_token) = tokens.next() {
+        let token = raw_token.to_string();
+        if token == *"type" {
+            let Some(TokenTree::Ident(name)) = tokens.next() else {
+                return Err(Error {
+                    start: raw_token.span(),
+                    end: raw_token.span(),
+                    kind: ErrorKind::UnnamedClass,
+                });
+            };
+            let mut superclass = None;
+            let mut next = tokens.next();
+            // Classes defined from Rust: `type MyClass: NSObject;`
+            if let Some(TokenTree::Punct(colon)) = &next {
+                if colon.as_char() == ':' {
+                    let Some(TokenTree::Ident(parent)) = tokens.next() else {
+                        return Err(Error {
+                            start: colon.span(),
+                            end: colon.span(),
+                            kind: ErrorKind::NoSuperclass,
+                        });
+                    };
+                    superclass = Some(parent.to_string());
+                    next = tokens.next();
+                }
+            }
+            let Some(TokenTree::Punct(semicolon)) = next else {
+                return Err(Error {
+                    start: raw_token.span(),
+                    end: name.span(),
+                    kind: ErrorKind::NoSemicolonAfterClass,
+                });
+            };
+            if semicolon.as_char() != ';' {
+                return Err(Error {
+                    start: raw_token.span(),
+                    end: name.span(),
+                    kind: ErrorKind::NoSemicolonAfterClass,
+                });
+            }
+
+            // `#[protocol] type NSWindowDelegate;` declares a protocol instead.
+            if active_attributes
+                .iter()
+                .any(|(attribute, _)| matches!(attribute, Attribute::Protocol))
+            {
+                let mut protocol = Protocol {
+                    name: name.to_string(),
+                    cfg: Vec::new(),
+                    attributes: Vec::new(),
+                    methods: Vec::new(),
+                };
+                for (attribute, span) in active_attributes.drain(..) {
+                    match attribute {
+                        Attribute::Protocol if superclass.is_none() => {}
+                        // Protocols don't have superclasses.
+                        Attribute::Protocol => {
+                            return Err(Error {
+                                start: span,
+                                end: span,
+                                kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                                    "`type`s without a superclass",
+                                )),
+                            })
+                        }
+                        Attribute::Cfg(cfg) => protocol.cfg.push(cfg),
+                        Attribute::Forward(attribute) => protocol.attributes.push(attribute),
+                        _ => {
+                            return Err(Error {
+                                start: span,
+                                end: span,
+                                kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                                    "classes and methods, not protocols",
+                                )),
+                            })
+                        }
+                    }
+                }
+                // Methods after the protocol are its required methods.
+                if let Some(old) = current_protocol.replace(protocol) {
+                    items.push(ParserOutput::Protocol(old));
+                }
+                if let Some(old) = current_class.take() {
+                    classes.insert(old);
+                }
+                continue;
+            }
+
+            let mut class = Class::new(name.to_string());
+            class.superclass = superclass;
+            let mut derive_span = None;
+            for (attribute, span) in active_attributes.drain(..) {
+                match attribute {
+                    Attribute::OnDrop(func) => class.on_drop = Some(func),
+                    Attribute::Description => class.description = true,
+                    Attribute::Comparable => class.comparable = true,
+                    Attribute::DeriveObjc(derives) => {
+                        for derive in derives {
+                            match derive {
+                                ObjcDerive::Eq => class.derive_eq = true,
+                                ObjcDerive::Hash => class.derive_hash = true,
+                            }
+                        }
+                        derive_span = Some(span);
+                    }
+                    Attribute::VTable(storage) => class.vtable_storage = storage,
+                    Attribute::Cfg(cfg) => class.cfg.push(cfg),
+                    Attribute::InstanceType(name) => class.instance_type = Some(name),
+                    Attribute::Ivar(name, ty) => class.ivars.push((name, ty)),
+                    // Only classes defined from Rust can adopt protocols or implement
+                    // methods.
+                    Attribute::Protocols(_) | Attribute::OnPanic(_)
+                        if class.superclass.is_none() =>
+                    {
+                        return Err(Error {
+                            start: span,
+                            end: span,
+                            kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                                "classes defined from Rust (with a superclass)",
+                            )),
+                        })
+                    }
+                    Attribute::Protocols(protocols) => class.protocols.extend(protocols),
+                    Attribute::ThreadSafe => class.thread_safe = true,
+                    Attribute::OnPanic(strategy) => class.panic_strategy = strategy,
+                    Attribute::Protocol => unreachable!(),
+                    Attribute::Forward(attribute) => class.attributes.push(attribute),
+                    Attribute::Selector(_)
+                    | Attribute::Cf(_)
+                    | Attribute::ExposeCmd
+                    | Attribute::ReturnsHandle(_)
+                    | Attribute::ReturnsAs(_)
+                    | Attribute::BoolIsSuccess
+                    | Attribute::Borrowed
+                    | Attribute::Nullable
+                    | Attribute::OutStruct
+                    | Attribute::Throws
+                    | Attribute::ErrorParam
+                    | Attribute::Singleton
+                    | Attribute::NilIsErr
+                    | Attribute::Property
+                    | Attribute::Variadic
+                    | Attribute::OutParams
+                    | Attribute::Constructor
+                    | Attribute::NeedsPool
+                    | Attribute::Cached => {
+                        return Err(Error {
+                            start: span,
+                            end: span,
+                            kind: ErrorKind::Attribute(AttributeError::WrongTarget("methods")),
+                        })
+                    }
+                }
+            }
+
+            if let Some(span) = derive_span {
+                // `#[comparable]` already implements `PartialEq`, with `compare:`.
+                if class.comparable && class.derive_eq {
+                    return Err(Error {
+                        start: span,
+                        end: span,
+                        kind: ErrorKind::Attribute(AttributeError::Conflict("comparable")),
+                    });
+                }
+                // `Hash` has to agree with `Eq`, which Objective-C's `hash` only does with
+                // `isEqual:` (or a `compare:` consistent with it).
+                if class.derive_hash && !class.derive_eq && !class.comparable {
+                    return Err(Error {
+                        start: span,
+                        end: span,
+                        kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                            "classes with `#[derive_objc(Eq)]` or `#[comparable]` to derive `Hash`",
+                        )),
+                    });
+                }
+            }
+
+            let old_class = current_class.replace(class);
+            if let Some(old) = old_class {
+                classes.insert(old);
+            }
+            if let Some(old) = current_protocol.take() {
+                items.push(ParserOutput::Protocol(old));
+            }
+        } else if token == *"static" {
+            let Some(TokenTree::Ident(name)) =
+                tokens.next().filter(|name| name.to_string() != "mut")
+            else {
+                return Err(Error {
+                    start: raw_token.span(),
+                    end: raw_token.span(),
+                    kind: ErrorKind::BadStatic,
+                });
+            };
+            if !matches!(tokens.next(), Some(TokenTree::Punct(colon)) if colon.as_char() == ':') {
+                return Err(Error {
+                    start: raw_token.span(),
+                    end: name.span(),
+                    kind: ErrorKind::BadStatic,
+                });
+            }
+            let mut ty = TokenStream::new();
+            loop {
+                match tokens.next() {
+                    Some(TokenTree::Punct(semicolon)) if semicolon.as_char() == ';' => break,
+                    Some(token) => ty.extend([token]),
+                    None => {
+                        return Err(Error {
+                            start: raw_token.span(),
+                            end: name.span(),
+                            kind: ErrorKind::BadStatic,
+                        })
+                    }
+                }
+            }
+            if ty.is_empty() {
+                return Err(Error {
+                    start: raw_token.span(),
+                    end: name.span(),
+                    kind: ErrorKind::BadStatic,
+                });
+            }
+
+            let mut item = Static {
+                name: name.to_string(),
+                ty: ty.to_string(),
+                cfg: Vec::new(),
+                attributes: Vec::new(),
+            };
+            for (attribute, span) in active_attributes.drain(..) {
+                match attribute {
+                    Attribute::Cfg(cfg) => item.cfg.push(cfg),
+                    Attribute::Forward(attribute) => item.attributes.push(attribute),
+                    _ => {
+                        return Err(Error {
+                            start: span,
+                            end: span,
+                            kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                                "classes and methods, not statics",
+                            )),
+                        })
+                    }
+                }
+            }
+            items.push(ParserOutput::Static(item));
+        } else if token == *"fn" {
+            let owner = match (&mut current_class, &mut current_protocol) {
+                (Some(class), _) => Some(MethodOwner::Class(class)),
+                (None, Some(protocol)) => Some(MethodOwner::Protocol(protocol)),
+                (None, None) => None,
+            };
+            function::parse_function(&mut tokens, raw_token.span(), owner, &active_attributes)?;
+            active_attributes.clear();
+        } else if token == *"#" {
+            let (attribute, span) = attribute::parse_attribute(&mut tokens, raw_token.span())?;
+            // `#[cfg]`s, `#[ivar]`s, and forwarded attributes can be stacked; ours can't
+            if !matches!(
+                attribute,
+                Attribute::Cfg(_) | Attribute::Ivar(_, _) | Attribute::Forward(_)
+            ) && active_attributes.iter().any(|(active, _)| {
+                std::mem::discriminant(active) == std::mem::discriminant(&attribute)
+            }) {
+                return Err(Error {
+                    start: span,
+                    end: span,
+                    kind: ErrorKind::Attribute(AttributeError::Duplicate),
+                });
+            }
+            active_attributes.push((attribute, span));
+        } else {
+            return Err(Error {
+                start: raw_token.span(),
+                end: raw_token.span(),
+                kind: ErrorKind::UnexpectedInBindingBlock,
+            });
+        }
+    }
+    if let Some(current) = current_class {
+        classes.insert(current);
+    }
+    if let Some(current) = current_protocol {
+        items.push(ParserOutput::Protocol(current));
+    }
+
+    Ok((classes.map.into_values().collect(), items))
+}
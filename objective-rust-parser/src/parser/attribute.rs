@@ -0,0 +1,495 @@
+use {
+    crate::{
+        Attribute, AttributeError, CfReturn, Error, ErrorKind, MacroConfig, ObjcDerive,
+        PanicStrategy, VTableStorage,
+    },
+    proc_macro::{Delimiter, Ident, Span, TokenStream, TokenTree},
+    std::iter::Peekable,
+};
+
+/// Parses an attribute, after its `#`. Returns the attribute and the span of its name.
+pub fn parse_attribute(
+    tokens: &mut Peekable<impl Iterator<Item = TokenTree>>,
+    hash_span: Span,
+) -> Result<(Attribute, Span), Error> {
+    let Some(TokenTree::Group(brackets)) = tokens.next() else {
+        return Err(Error {
+            start: hash_span,
+            end: hash_span,
+            kind: ErrorKind::Attribute(AttributeError::NoBrackets),
+        });
+    };
+
+    let mut tokens = brackets.stream().into_iter();
+    let Some(TokenTree::Ident(name)) = tokens.next() else {
+        return Err(Error {
+            start: brackets.span_open(),
+            end: brackets.span_open(),
+            kind: ErrorKind::Attribute(AttributeError::NoName),
+        });
+    };
+
+    let attribute = match name.to_string().as_str() {
+        "selector" => {
+            let selector = parse_string_value(&mut tokens, name.span())?;
+            Attribute::Selector(selector)
+        }
+        // `#[selector_parts(init_with_content_rect, style_mask)]` is
+        // `#[selector = "initWithContentRect:styleMask:"]`
+        "selector_parts" => {
+            let selector = parse_selector_parts(&mut tokens, name.span())?;
+            Attribute::Selector(selector)
+        }
+        "on_drop" => {
+            let func = parse_path_value(&mut tokens, name.span())?;
+            Attribute::OnDrop(func)
+        }
+        "description" => Attribute::Description,
+        "comparable" => Attribute::Comparable,
+        "derive_objc" => {
+            let derives = parse_derives(&mut tokens, name.span())?;
+            Attribute::DeriveObjc(derives)
+        }
+        "expose_cmd" => Attribute::ExposeCmd,
+        "borrowed" => Attribute::Borrowed,
+        "nullable" => Attribute::Nullable,
+        "out_struct" => Attribute::OutStruct,
+        "throws" => Attribute::Throws,
+        "error_param" => Attribute::ErrorParam,
+        "singleton" => Attribute::Singleton,
+        "nil_is_err" => Attribute::NilIsErr,
+        "property" => Attribute::Property,
+        "variadic" => Attribute::Variadic,
+        "out_params" => Attribute::OutParams,
+        "constructor" => Attribute::Constructor,
+        "needs_pool" => Attribute::NeedsPool,
+        "cached" => Attribute::Cached,
+        "bool_is_success" => Attribute::BoolIsSuccess,
+        "protocol" => Attribute::Protocol,
+        "thread_safe" => Attribute::ThreadSafe,
+        "protocols" => {
+            let protocols = parse_protocols(&mut tokens, name.span())?;
+            Attribute::Protocols(protocols)
+        }
+        "ivar" => {
+            let (name, ty) = parse_ivar(&mut tokens, name.span())?;
+            Attribute::Ivar(name, ty)
+        }
+        "returns_as" => {
+            let ty = parse_parenthesized_path(&mut tokens, name.span())?;
+            Attribute::ReturnsAs(ty)
+        }
+        "returns_handle" => {
+            let handle = parse_parenthesized_path(&mut tokens, name.span())?;
+            Attribute::ReturnsHandle(handle)
+        }
+        "instance_type" => {
+            let name = parse_string_value(&mut tokens, name.span())?;
+            Attribute::InstanceType(name)
+        }
+        "cf" => {
+            let rule = parse_parenthesized_ident(&mut tokens, name.span())?;
+            match rule.to_string().as_str() {
+                "create" => Attribute::Cf(CfReturn::Create),
+                "get" => Attribute::Cf(CfReturn::Get),
+                _ => {
+                    return Err(Error {
+                        start: rule.span(),
+                        end: rule.span(),
+                        kind: ErrorKind::Attribute(AttributeError::UnknownValue(
+                            "`create` or `get`",
+                        )),
+                    })
+                }
+            }
+        }
+        "cfg" | "cfg_attr" => Attribute::Cfg(format!("#[{}]", brackets.stream())),
+        "vtable" => {
+            let storage = parse_parenthesized_ident(&mut tokens, name.span())?;
+            match storage.to_string().as_str() {
+                "thread_local" => Attribute::VTable(VTableStorage::ThreadLocal),
+                "global" => Attribute::VTable(VTableStorage::Global),
+                _ => {
+                    return Err(Error {
+                        start: storage.span(),
+                        end: storage.span(),
+                        kind: ErrorKind::Attribute(AttributeError::UnknownValue(
+                            "`thread_local` or `global`",
+                        )),
+                    })
+                }
+            }
+        }
+        "on_panic" => {
+            let strategy = parse_parenthesized_ident(&mut tokens, name.span())?;
+            match strategy.to_string().as_str() {
+                "abort" => Attribute::OnPanic(PanicStrategy::Abort),
+                "throw" => Attribute::OnPanic(PanicStrategy::Throw),
+                _ => {
+                    return Err(Error {
+                        start: strategy.span(),
+                        end: strategy.span(),
+                        kind: ErrorKind::Attribute(AttributeError::UnknownValue(
+                            "`abort` or `throw`",
+                        )),
+                    })
+                }
+            }
+        }
+        // Anything objective-rust doesn't use itself is passed on to rustc
+        _ => Attribute::Forward(format!("#[{}]", brackets.stream())),
+    };
+
+    Ok((attribute, name.span()))
+}
+
+/// Parses the arguments of the `objrs` macro itself:
+/// `#[objrs(framework = "AppKit", enums(NSComparisonResult))]`. Both can be given more
+/// than once.
+pub fn parse_macro_args(args: TokenStream) -> Result<MacroConfig, Error> {
+    let mut config = MacroConfig::default();
+    let mut tokens = args.into_iter();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(name) if name.to_string() == "framework" => {
+                let framework = parse_string_value(&mut tokens, name.span())?;
+                config.frameworks.push(framework);
+            }
+            TokenTree::Ident(name) if name.to_string() == "enums" => {
+                let enums = parse_parenthesized_path(&mut tokens, name.span())?;
+                config.enums.extend(
+                    enums
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|ty| !ty.is_empty())
+                        .map(String::from),
+                );
+            }
+            other => {
+                return Err(Error {
+                    start: other.span(),
+                    end: other.span(),
+                    kind: ErrorKind::UnknownMacroArgument,
+                })
+            }
+        }
+
+        match tokens.next() {
+            None => break,
+            Some(TokenTree::Punct(comma)) if comma.as_char() == ',' => {}
+            Some(other) => {
+                return Err(Error {
+                    start: other.span(),
+                    end: other.span(),
+                    kind: ErrorKind::UnknownMacroArgument,
+                })
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+/// Parses the `=` after an attribute's name.
+fn parse_equals(
+    tokens: &mut impl Iterator<Item = TokenTree>,
+    name_span: Span,
+) -> Result<Span, Error> {
+    let Some(TokenTree::Punct(equals)) = tokens.next() else {
+        return Err(Error {
+            start: name_span,
+            end: name_span,
+            kind: ErrorKind::Attribute(AttributeError::NoEquals),
+        });
+    };
+    if equals.as_char() != '=' {
+        return Err(Error {
+            start: equals.span(),
+            end: equals.span(),
+            kind: ErrorKind::Attribute(AttributeError::NoEquals),
+        });
+    }
+
+    Ok(equals.span())
+}
+
+/// Parses `= "value"` in an attribute, and returns the value without quotes.
+fn parse_string_value(
+    tokens: &mut impl Iterator<Item = TokenTree>,
+    name_span: Span,
+) -> Result<String, Error> {
+    let equals_span = parse_equals(tokens, name_span)?;
+
+    let Some(TokenTree::Literal(value)) = tokens.next() else {
+        return Err(Error {
+            start: equals_span,
+            end: equals_span,
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    };
+    let text = value.to_string();
+    if text.as_bytes()[0] != b'"' || text.as_bytes()[text.len() - 1] != b'"' {
+        return Err(Error {
+            start: value.span(),
+            end: value.span(),
+            kind: ErrorKind::Attribute(AttributeError::Type("String".into())),
+        });
+    }
+
+    Ok(text[1..text.len() - 1].into())
+}
+
+/// Parses `(part, part, ...)` in a `selector_parts` attribute, and returns the selector
+/// they make: each part converted from snake_case to camelCase, followed by a `:`.
+fn parse_selector_parts(
+    tokens: &mut impl Iterator<Item = TokenTree>,
+    name_span: Span,
+) -> Result<String, Error> {
+    let Some(TokenTree::Group(parens)) = tokens.next() else {
+        return Err(Error {
+            start: name_span,
+            end: name_span,
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    };
+    if parens.delimiter() != Delimiter::Parenthesis || parens.stream().is_empty() {
+        return Err(Error {
+            start: parens.span(),
+            end: parens.span(),
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    }
+
+    let mut selector = String::new();
+    let mut expect_part = true;
+    for token in parens.stream() {
+        match token {
+            TokenTree::Ident(part) if expect_part => {
+                let part = part.to_string();
+                let part = part.strip_prefix("r#").unwrap_or(&part);
+                // Leading underscores are part of the name, like in `_setThing:`
+                let name = part.trim_start_matches('_');
+                selector += &part[..part.len() - name.len()];
+
+                let mut words = name.split('_');
+                selector += words.next().unwrap_or_default();
+                for word in words {
+                    let mut chars = word.chars();
+                    if let Some(first) = chars.next() {
+                        selector.extend(first.to_uppercase());
+                    }
+                    selector.extend(chars);
+                }
+                selector.push(':');
+            }
+            TokenTree::Punct(comma) if !expect_part && comma.as_char() == ',' => {}
+            other => {
+                return Err(Error {
+                    start: other.span(),
+                    end: other.span(),
+                    kind: ErrorKind::Attribute(AttributeError::Type("identifier".into())),
+                })
+            }
+        }
+        expect_part = !expect_part;
+    }
+
+    Ok(selector)
+}
+
+/// Parses `= some::path` in an attribute, and returns the path.
+fn parse_path_value(
+    tokens: &mut impl Iterator<Item = TokenTree>,
+    name_span: Span,
+) -> Result<String, Error> {
+    let equals_span = parse_equals(tokens, name_span)?;
+
+    let path = TokenStream::from_iter(tokens);
+    if path.is_empty() {
+        return Err(Error {
+            start: equals_span,
+            end: equals_span,
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    }
+
+    Ok(path.to_string())
+}
+
+/// Parses `(some::path)` in an attribute, and returns the path.
+fn parse_parenthesized_path(
+    tokens: &mut impl Iterator<Item = TokenTree>,
+    name_span: Span,
+) -> Result<String, Error> {
+    let Some(TokenTree::Group(parens)) = tokens.next() else {
+        return Err(Error {
+            start: name_span,
+            end: name_span,
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    };
+    if parens.delimiter() != Delimiter::Parenthesis || parens.stream().is_empty() {
+        return Err(Error {
+            start: parens.span(),
+            end: parens.span(),
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    }
+
+    Ok(parens.stream().to_string())
+}
+
+/// Parses the name and type in `#[ivar(name: Type)]`.
+fn parse_ivar(
+    tokens: &mut impl Iterator<Item = TokenTree>,
+    name_span: Span,
+) -> Result<(String, String), Error> {
+    let Some(TokenTree::Group(parens)) = tokens.next() else {
+        return Err(Error {
+            start: name_span,
+            end: name_span,
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    };
+    let no_value = Error {
+        start: parens.span(),
+        end: parens.span(),
+        kind: ErrorKind::Attribute(AttributeError::NoValue),
+    };
+    if parens.delimiter() != Delimiter::Parenthesis {
+        return Err(no_value);
+    }
+
+    let mut tokens = parens.stream().into_iter();
+    let Some(TokenTree::Ident(ivar)) = tokens.next() else {
+        return Err(no_value);
+    };
+    match tokens.next() {
+        Some(TokenTree::Punct(colon)) if colon.as_char() == ':' => {}
+        _ => return Err(no_value),
+    }
+    let ty = tokens.collect::<TokenStream>();
+    if ty.is_empty() {
+        return Err(no_value);
+    }
+
+    Ok((ivar.to_string(), ty.to_string()))
+}
+
+/// Parses the protocol names in `#[protocols(NSWindowDelegate, ...)]`.
+fn parse_protocols(
+    tokens: &mut impl Iterator<Item = TokenTree>,
+    name_span: Span,
+) -> Result<Vec<String>, Error> {
+    let Some(TokenTree::Group(parens)) = tokens.next() else {
+        return Err(Error {
+            start: name_span,
+            end: name_span,
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    };
+    if parens.delimiter() != Delimiter::Parenthesis || parens.stream().is_empty() {
+        return Err(Error {
+            start: parens.span(),
+            end: parens.span(),
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    }
+
+    let mut protocols = Vec::new();
+    for token in parens.stream() {
+        match token {
+            TokenTree::Ident(protocol) => protocols.push(protocol.to_string()),
+            TokenTree::Punct(comma) if comma.as_char() == ',' => {}
+            other => {
+                return Err(Error {
+                    start: other.span(),
+                    end: other.span(),
+                    kind: ErrorKind::Attribute(AttributeError::Type("identifier".into())),
+                })
+            }
+        }
+    }
+
+    Ok(protocols)
+}
+
+/// Parses the traits in `#[derive_objc(Eq, ...)]`.
+fn parse_derives(
+    tokens: &mut impl Iterator<Item = TokenTree>,
+    name_span: Span,
+) -> Result<Vec<ObjcDerive>, Error> {
+    let Some(TokenTree::Group(parens)) = tokens.next() else {
+        return Err(Error {
+            start: name_span,
+            end: name_span,
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    };
+    if parens.delimiter() != Delimiter::Parenthesis || parens.stream().is_empty() {
+        return Err(Error {
+            start: parens.span(),
+            end: parens.span(),
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    }
+
+    let mut derives = Vec::new();
+    for token in parens.stream() {
+        match token {
+            TokenTree::Ident(derive) => match derive.to_string().as_str() {
+                "Eq" => derives.push(ObjcDerive::Eq),
+                "Hash" => derives.push(ObjcDerive::Hash),
+                _ => {
+                    return Err(Error {
+                        start: derive.span(),
+                        end: derive.span(),
+                        kind: ErrorKind::Attribute(AttributeError::UnknownValue("`Eq` or `Hash`")),
+                    })
+                }
+            },
+            TokenTree::Punct(comma) if comma.as_char() == ',' => {}
+            other => {
+                return Err(Error {
+                    start: other.span(),
+                    end: other.span(),
+                    kind: ErrorKind::Attribute(AttributeError::NoValue),
+                })
+            }
+        }
+    }
+
+    Ok(derives)
+}
+
+/// Parses `(value)` in an attribute, and returns the value.
+fn parse_parenthesized_ident(
+    tokens: &mut impl Iterator<Item = TokenTree>,
+    name_span: Span,
+) -> Result<Ident, Error> {
+    let Some(TokenTree::Group(parens)) = tokens.next() else {
+        return Err(Error {
+            start: name_span,
+            end: name_span,
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    };
+    if parens.delimiter() != Delimiter::Parenthesis {
+        return Err(Error {
+            start: parens.span(),
+            end: parens.span(),
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        });
+    }
+
+    let mut inner = parens.stream().into_iter();
+    match (inner.next(), inner.next()) {
+        (Some(TokenTree::Ident(value)), None) => Ok(value),
+        _ => Err(Error {
+            start: parens.span(),
+            end: parens.span(),
+            kind: ErrorKind::Attribute(AttributeError::NoValue),
+        }),
+    }
+}
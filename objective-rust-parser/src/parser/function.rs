@@ -0,0 +1,1001 @@
+use {
+    crate::{Argument, Attribute, Class, Function, Protocol, SelfReference, Type},
+    crate::{AttributeError, Error, ErrorKind, MethodError},
+    proc_macro::{Delimiter, Span, TokenTree},
+    std::iter::Peekable,
+};
+
+/// What a method is declared on: the class or protocol declared before it.
+pub enum MethodOwner<'a> {
+    Class(&'a mut Class),
+    Protocol(&'a mut Protocol),
+}
+
+pub fn parse_function(
+    tokens: &mut Peekable<impl Iterator<Item = TokenTree>>,
+    start_span: Span,
+    owner: Option<MethodOwner>,
+    attributes: &[(Attribute, Span)],
+) -> Result<(), Error> {
+    let Some(TokenTree::Ident(fn_name)) = tokens.next() else {
+        return Err(Error {
+            start: start_span,
+            end: start_span,
+            kind: ErrorKind::Method(MethodError::NoName),
+        });
+    };
+    let Some(TokenTree::Group(fn_args)) = tokens.next() else {
+        return Err(Error {
+            start: fn_name.span(),
+            end: fn_name.span(),
+            kind: ErrorKind::Method(MethodError::NoArgs),
+        });
+    };
+    if fn_args.delimiter() != Delimiter::Parenthesis {
+        return Err(Error {
+            start: fn_name.span(),
+            end: fn_name.span(),
+            kind: ErrorKind::Method(MethodError::NoArgs),
+        });
+    }
+
+    // Methods end with a `;`, or with a body if they're implemented in Rust.
+    let mut body = None;
+    let return_type = match tokens.next() {
+        Some(TokenTree::Punct(semicolon)) if semicolon.as_char() == ';' => None,
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+            body = Some(group);
+            None
+        }
+        Some(TokenTree::Punct(dash)) if dash.as_char() == '-' => {
+            let Some(TokenTree::Punct(maybe_arrow)) = tokens.next() else {
+                return Err(Error {
+                    start: fn_args.span(),
+                    end: fn_args.span(),
+                    kind: ErrorKind::Method(MethodError::NoReturnTypeOrSemicolon),
+                });
+            };
+            if maybe_arrow.as_char() != '>' {
+                return Err(Error {
+                    start: fn_args.span(),
+                    end: fn_args.span(),
+                    kind: ErrorKind::Method(MethodError::NoReturnTypeOrSemicolon),
+                });
+            }
+
+            let ty = crate::parser::parse_type(tokens, maybe_arrow.span())?;
+            // There's no way to know how long a returned borrow lives for
+            if let Type::Borrow(_, _, span) | Type::Block(_, span) = ty {
+                return Err(Error {
+                    start: span,
+                    end: span,
+                    kind: ErrorKind::BorrowsUnsupported,
+                });
+            }
+
+            match tokens.next() {
+                Some(TokenTree::Punct(semicolon)) if semicolon.as_char() == ';' => {}
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+                    body = Some(group)
+                }
+                _ => {
+                    return Err(Error {
+                        start: ty.span(),
+                        end: ty.span(),
+                        kind: ErrorKind::Method(MethodError::NoSemicolon),
+                    })
+                }
+            }
+
+            Some(ty)
+        }
+        _ => {
+            return Err(Error {
+                start: fn_args.span(),
+                end: fn_args.span(),
+                kind: ErrorKind::Method(MethodError::NoReturnTypeOrSemicolon),
+            });
+        }
+    };
+
+    let Some(owner) = owner else {
+        return Err(Error {
+            start: start_span,
+            end: fn_args.span(),
+            kind: ErrorKind::MethodBeforeClass,
+        });
+    };
+
+    let (self_reference, args) =
+        parse_args(fn_args.stream().into_iter().peekable(), fn_args.span_open())?;
+
+    let mut func = Function {
+        name: fn_name.to_string(),
+        return_type,
+        args,
+        self_reference,
+        selector: None,
+        cfg: Vec::new(),
+        cf_return: None,
+        expose_cmd: false,
+        handle_return: None,
+        returns_as: None,
+        bool_is_success: false,
+        borrowed_return: false,
+        nullable: false,
+        out_struct: false,
+        throws: false,
+        error_param: false,
+        singleton: false,
+        nil_is_err: false,
+        variadic: false,
+        out_params: false,
+        constructor: false,
+        needs_pool: false,
+        cached: false,
+        body,
+        attributes: Vec::new(),
+    };
+
+    if func.returns_borrowed_string()
+        && matches!(
+            func.self_reference,
+            SelfReference::None | SelfReference::Owned
+        )
+    {
+        return Err(Error {
+            start: fn_name.span(),
+            end: fn_args.span(),
+            kind: ErrorKind::Method(MethodError::BorrowWithoutSelf),
+        });
+    }
+
+    let mut nil_is_err_span = None;
+    let mut property_span = None;
+    let mut out_params_span = None;
+    let mut constructor_span = None;
+    let mut selector_span = None;
+    for (attribute, span) in attributes {
+        match attribute {
+            // Checked after the other attributes, which can make the method return an
+            // `Option`.
+            Attribute::NilIsErr => nil_is_err_span = Some(*span),
+            Attribute::Property => property_span = Some(*span),
+            // Checked after the other attributes, since the selector is needed.
+            Attribute::OutParams => out_params_span = Some(*span),
+            // Checked after the other attributes, which can change what the method
+            // returns.
+            Attribute::Constructor => constructor_span = Some(*span),
+            Attribute::Variadic => {
+                // Variadic arguments go through C's default argument promotions, which
+                // only leave pointers untouched.
+                let last_is_pointer_slice = matches!(
+                    func.args.last(),
+                    Some(Argument {
+                        ty: Type::Slice(ty, _),
+                        consumed: false,
+                        ..
+                    }) if matches!(**ty, Type::Pointer(_, _, _))
+                );
+                if !last_is_pointer_slice {
+                    return Err(Error {
+                        start: *span,
+                        end: *span,
+                        kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                            "methods whose last argument is a slice of pointers",
+                        )),
+                    });
+                }
+                func.variadic = true;
+            }
+            Attribute::Selector(sel) => {
+                func.selector = Some(sel.clone());
+                selector_span = Some(*span);
+            }
+            Attribute::Cfg(cfg) => func.cfg.push(cfg.clone()),
+            Attribute::Forward(attribute) => func.attributes.push(attribute.clone()),
+            Attribute::ExposeCmd => func.expose_cmd = true,
+            Attribute::NeedsPool => func.needs_pool = true,
+            Attribute::Cached => {
+                if !func.returns_borrowed_string()
+                    || func.self_reference != SelfReference::Immutable
+                    || !func.args.is_empty()
+                {
+                    return Err(Error {
+                        start: *span,
+                        end: *span,
+                        kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                            "`&self` methods that take no arguments and return an `NSStr`",
+                        )),
+                    });
+                }
+                func.cached = true;
+            }
+            Attribute::Throws => func.throws = true,
+            Attribute::Singleton => {
+                if func.self_reference != SelfReference::None
+                    || !func
+                        .return_type
+                        .as_ref()
+                        .is_some_and(Type::is_self_or_optional_self)
+                {
+                    return Err(Error {
+                        start: *span,
+                        end: *span,
+                        kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                            "class methods that return `Self`",
+                        )),
+                    });
+                }
+                func.singleton = true;
+            }
+            Attribute::ErrorParam => {
+                let last_is_pointer = matches!(
+                    func.args.last(),
+                    Some(Argument {
+                        ty: Type::Pointer(_, _, _),
+                        consumed: false,
+                        ..
+                    })
+                );
+                if !last_is_pointer {
+                    return Err(Error {
+                        start: *span,
+                        end: *span,
+                        kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                            "methods whose last argument is a pointer",
+                        )),
+                    });
+                }
+                func.error_param = true;
+            }
+            Attribute::OutStruct => {
+                let returns_struct = matches!(
+                    &func.return_type,
+                    Some(ret @ Type::Absolute(ty, _)) if ty != "Self" && !ret.is_any_bool()
+                );
+                if !returns_struct
+                    || func.self_reference == SelfReference::Owned
+                    || func.returns_borrowed_string()
+                {
+                    return Err(Error {
+                        start: *span,
+                        end: *span,
+                        kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                            "methods that return a struct",
+                        )),
+                    });
+                }
+                func.out_struct = true;
+            }
+            Attribute::Nullable => {
+                if !matches!(func.return_type, Some(Type::Pointer(_, _, _))) {
+                    return Err(Error {
+                        start: *span,
+                        end: *span,
+                        kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                            "methods that return a pointer",
+                        )),
+                    });
+                }
+                func.nullable = true;
+            }
+            Attribute::Borrowed => {
+                if !matches!(func.return_type, Some(Type::Absolute(_, _)))
+                    || func.returns_borrowed_string()
+                {
+                    return Err(Error {
+                        start: *span,
+                        end: *span,
+                        kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                            "methods that return a bound class",
+                        )),
+                    });
+                }
+                if matches!(
+                    func.self_reference,
+                    SelfReference::None | SelfReference::Owned
+                ) {
+                    return Err(Error {
+                        start: *span,
+                        end: *span,
+                        kind: ErrorKind::Method(MethodError::BorrowWithoutSelf),
+                    });
+                }
+                func.borrowed_return = true;
+            }
+            Attribute::BoolIsSuccess => {
+                if !func.return_type.as_ref().is_some_and(Type::is_any_bool) {
+                    return Err(Error {
+                        start: *span,
+                        end: *span,
+                        kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                            "methods that return a `bool`",
+                        )),
+                    });
+                }
+                func.bool_is_success = true;
+            }
+            Attribute::ReturnsHandle(handle) => {
+                if !matches!(func.return_type, Some(Type::Pointer(_, _, _)))
+                    || func.self_reference == SelfReference::Owned
+                {
+                    return Err(Error {
+                        start: *span,
+                        end: *span,
+                        kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                            "methods that return a pointer",
+                        )),
+                    });
+                }
+                func.handle_return = Some(handle.clone());
+            }
+            Attribute::ReturnsAs(ty) => {
+                if !matches!(&func.return_type, Some(Type::Absolute(ty, _)) if ty != "Self")
+                    || func.returns_borrowed_string()
+                {
+                    return Err(Error {
+                        start: *span,
+                        end: *span,
+                        kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                            "methods that return a primitive",
+                        )),
+                    });
+                }
+                func.returns_as = Some(ty.clone());
+            }
+            Attribute::Cf(cf_return) => {
+                if !matches!(func.return_type, Some(Type::Absolute(_, _)))
+                    || func.self_reference == SelfReference::Owned
+                {
+                    return Err(Error {
+                        start: *span,
+                        end: *span,
+                        kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                            "methods that return a bound class",
+                        )),
+                    });
+                }
+                func.cf_return = Some(*cf_return);
+            }
+            Attribute::OnDrop(_)
+            | Attribute::Description
+            | Attribute::Comparable
+            | Attribute::DeriveObjc(_)
+            | Attribute::VTable(_)
+            | Attribute::InstanceType(_)
+            | Attribute::Ivar(_, _)
+            | Attribute::Protocols(_)
+            | Attribute::Protocol
+            | Attribute::ThreadSafe
+            | Attribute::OnPanic(_) => {
+                return Err(Error {
+                    start: *span,
+                    end: *span,
+                    kind: ErrorKind::Attribute(AttributeError::WrongTarget("classes")),
+                })
+            }
+        }
+    }
+
+    // Combinations of attributes are checked once they've all been seen, so the order
+    // they're written in doesn't matter.
+    let names: Vec<_> = attributes
+        .iter()
+        .filter_map(|(attribute, span)| Some((attribute_name(attribute)?, *span)))
+        .collect();
+    let (names, spans): (Vec<_>, Vec<_>) = names.into_iter().unzip();
+    if let Some((earlier, later)) = find_conflict(&names) {
+        return Err(Error {
+            start: spans[later],
+            end: spans[later],
+            kind: ErrorKind::Attribute(AttributeError::Conflict(names[earlier])),
+        });
+    }
+
+    if let Some(span) = out_params_span {
+        // Each value is written by the method through a pointer, so it has to be a plain
+        // value or pointer that starts out valid (zero or null).
+        let values = match &func.return_type {
+            Some(Type::Tuple(values, _)) if !values.is_empty() => Some(values),
+            _ => None,
+        };
+        let supported = |ty: &Type| match ty {
+            Type::Absolute(ty, _) => ty != "Self" && ty != "bool",
+            Type::Pointer(_, _, _) => true,
+            _ => false,
+        };
+        let values = values.filter(|values| values.iter().all(supported));
+        let Some(values) = values.filter(|_| func.self_reference != SelfReference::Owned) else {
+            return Err(Error {
+                start: span,
+                end: span,
+                kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                    "methods that return a tuple of values or pointers",
+                )),
+            });
+        };
+        // The out-params are the selector's last arguments. Implicit selectors (the
+        // method's name) don't have any `:`s, so they can't be used.
+        let colons = func.selector.as_deref().unwrap_or("").matches(':').count();
+        if colons != func.args.len() + values.len() {
+            return Err(Error {
+                start: fn_name.span(),
+                end: fn_args.span(),
+                kind: ErrorKind::Method(MethodError::OutParamsArity),
+            });
+        }
+        func.out_params = true;
+    }
+
+    // `#[out_params]` checks its own selector, and `#[expose_cmd]` methods can be
+    // called with any selector.
+    if let (Some(span), Some(selector)) = (selector_span, &func.selector) {
+        if !func.out_params && !func.expose_cmd {
+            if let Err(err) = check_selector_arity(selector, objc_arg_count(&func)) {
+                return Err(Error {
+                    start: span,
+                    end: span,
+                    kind: ErrorKind::Method(err),
+                });
+            }
+        }
+    }
+
+    if let Some(span) = nil_is_err_span {
+        let returns_option = matches!(func.return_type, Some(Type::Option(_, _)))
+            || func.nullable
+            || func.handle_return.is_some()
+            || func.borrowed_return
+            || func.cf_return.is_some();
+        if !returns_option {
+            return Err(Error {
+                start: span,
+                end: span,
+                kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                    "methods that return an `Option`",
+                )),
+            });
+        }
+        func.nil_is_err = true;
+    }
+
+    if let Some(span) = constructor_span {
+        // The instance is allocated by the generated function, and `init` methods
+        // return nil when they fail, so the function returns `Option<Self>`.
+        let returns_optional_self = matches!(
+            &func.return_type,
+            Some(Type::Option(ty, _)) if matches!(&**ty, Type::Absolute(ty, _) if ty == "Self")
+        );
+        if !returns_optional_self || func.self_reference != SelfReference::None {
+            return Err(Error {
+                start: span,
+                end: span,
+                kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                    "methods that don't take `self` and return `Option<Self>`",
+                )),
+            });
+        }
+        func.constructor = true;
+    }
+
+    let in_protocol = matches!(owner, MethodOwner::Protocol(_));
+    match &owner {
+        MethodOwner::Class(class) if func.body.is_some() && class.superclass.is_none() => {
+            return Err(Error {
+                start: fn_name.span(),
+                end: fn_args.span(),
+                kind: ErrorKind::Method(MethodError::BodyWithoutSuperclass),
+            });
+        }
+        MethodOwner::Protocol(_) if func.body.is_some() => {
+            return Err(Error {
+                start: fn_name.span(),
+                end: fn_args.span(),
+                kind: ErrorKind::Method(MethodError::BodyInProtocol),
+            });
+        }
+        _ => {}
+    }
+
+    // Protocol methods are implemented in Rust too, by the classes that adopt them.
+    if func.body.is_some() || in_protocol {
+        // The method is called from Objective-C with exactly the types it's declared
+        // with, so only types that don't need converting (besides `bool`) can be used.
+        // In a protocol's trait, `Self` is the adopting class' wrapper, not an instance,
+        // so it can't be used at all.
+        let supported = |ty: &Type| match ty {
+            Type::Absolute(ty, _) => ty != "Self",
+            Type::Pointer(_, ty, _) if in_protocol => {
+                !matches!(&**ty, Type::Absolute(ty, _) if ty == "Self")
+            }
+            Type::Pointer(_, _, _) => true,
+            _ => false,
+        };
+        if func.self_reference == SelfReference::Owned
+            || !func
+                .args
+                .iter()
+                .all(|arg| !arg.consumed && supported(&arg.ty))
+            || !func.return_type.iter().all(supported)
+            || func.cf_return.is_some()
+            || func.expose_cmd
+            || func.handle_return.is_some()
+            || func.returns_as.is_some()
+            || func.bool_is_success
+            || func.borrowed_return
+            || func.nullable
+            || func.out_struct
+            || func.throws
+            || func.error_param
+            || func.singleton
+            || func.nil_is_err
+            || func.variadic
+            || func.constructor
+            || func.needs_pool
+            || func.cached
+            || func.returns_borrowed_string()
+        {
+            return Err(Error {
+                start: fn_name.span(),
+                end: fn_args.span(),
+                kind: ErrorKind::Method(MethodError::UnsupportedInImplementation),
+            });
+        }
+    }
+
+    // `#[property] fn hidden(&self) -> bool;` also binds `fn set_hidden(&mut self, value: bool);`
+    // to `setHidden:`.
+    let setter = match (property_span, &func.return_type) {
+        (None, _) => None,
+        (Some(_), Some(ty))
+            if func.self_reference == SelfReference::Immutable
+                && func.args.is_empty()
+                && func.body.is_none()
+                && !func.expose_cmd
+                && !func.out_struct =>
+        {
+            let mut selector = String::from("set");
+            for word in func.name.trim_start_matches("r#").split('_') {
+                let mut chars = word.chars();
+                if let Some(first) = chars.next() {
+                    selector.extend(first.to_uppercase());
+                }
+                selector.extend(chars);
+            }
+            selector.push(':');
+
+            // The getter's docs don't describe the setter, but everything else (like
+            // `#[deprecated]`) applies to both.
+            let mut attributes = vec![format!("#[doc = \" Sets the `{}` property.\"]", func.name)];
+            attributes.extend(
+                func.attributes
+                    .iter()
+                    .filter(|attr| !attr.starts_with("#[doc"))
+                    .cloned(),
+            );
+
+            Some(Function {
+                name: format!("set_{}", func.name.trim_start_matches("r#")),
+                return_type: None,
+                // Objects are taken by reference: the Objective-C setter retains its
+                // own reference, so moving the caller's into the call would leak it.
+                args: vec![Argument {
+                    name: "value".into(),
+                    ty: ty.clone(),
+                    consumed: false,
+                    by_ref: !ty.is_primitive(),
+                }],
+                self_reference: SelfReference::Mutable,
+                selector: Some(selector),
+                cfg: func.cfg.clone(),
+                cf_return: None,
+                expose_cmd: false,
+                handle_return: None,
+                returns_as: None,
+                bool_is_success: false,
+                borrowed_return: false,
+                nullable: false,
+                out_struct: false,
+                throws: func.throws,
+                error_param: false,
+                singleton: false,
+                nil_is_err: false,
+                variadic: false,
+                out_params: false,
+                constructor: false,
+                needs_pool: false,
+                cached: false,
+                body: None,
+                attributes,
+            })
+        }
+        (Some(span), _) => {
+            return Err(Error {
+                start: span,
+                end: span,
+                kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                    "getters that take `&self` and return a value",
+                )),
+            })
+        }
+    };
+    let methods = match owner {
+        MethodOwner::Class(class) => &mut class.methods,
+        MethodOwner::Protocol(protocol) => &mut protocol.methods,
+    };
+    methods.push(func);
+    methods.extend(setter);
+
+    Ok(())
+}
+
+/// The number of arguments a method passes to Objective-C, which is how many `:`s its
+/// selector needs.
+fn objc_arg_count(func: &Function) -> usize {
+    let variadic_index = func.args.len().checked_sub(1).filter(|_| func.variadic);
+    let args: usize = func
+        .args
+        .iter()
+        .enumerate()
+        .map(|(idx, arg)| match arg.ty {
+            // Slices are passed as a pointer and a length, besides variadic ones, which
+            // are spread after the selector's last argument.
+            Type::Slice(_, _) if !arg.consumed && Some(idx) != variadic_index => 2,
+            _ => 1,
+        })
+        .sum();
+    args + usize::from(func.out_struct)
+}
+
+/// Checks that `selector` has a `:` for each of the `args` arguments the method passes to
+/// Objective-C.
+fn check_selector_arity(selector: &str, args: usize) -> Result<(), MethodError> {
+    let colons = selector.matches(':').count();
+    match colons == args {
+        true => Ok(()),
+        false => Err(MethodError::SelectorArity { colons, args }),
+    }
+}
+
+fn parse_args(
+    mut src: Peekable<impl Iterator<Item = TokenTree>>,
+    mut last_span: Span,
+) -> Result<(SelfReference, Vec<Argument>), Error> {
+    let Some(maybe_self) = src.peek() else {
+        return Ok((SelfReference::None, Vec::new()));
+    };
+    let maybe_self = maybe_self.to_string();
+
+    let self_reference = if maybe_self == *"&" {
+        let ref_token = src.next().unwrap();
+        let Some(TokenTree::Ident(maybe_self)) = src.next() else {
+            return Err(Error {
+                start: ref_token.span(),
+                end: ref_token.span(),
+                kind: ErrorKind::Method(MethodError::ExpectedSelfReference),
+            });
+        };
+        match maybe_self.to_string().as_str() {
+            "self" => {
+                last_span = maybe_self.span();
+                SelfReference::Immutable
+            }
+            "mut" => {
+                let Some(TokenTree::Ident(_self)) = src.next() else {
+                    return Err(Error {
+                        start: ref_token.span(),
+                        end: ref_token.span(),
+                        kind: ErrorKind::Method(MethodError::ExpectedSelfReference),
+                    });
+                };
+                if _self.to_string() != *"self" {
+                    return Err(Error {
+                        start: ref_token.span(),
+                        end: ref_token.span(),
+                        kind: ErrorKind::Method(MethodError::ExpectedSelfReference),
+                    });
+                }
+
+                last_span = _self.span();
+                SelfReference::Mutable
+            }
+            _ => {
+                return Err(Error {
+                    start: ref_token.span(),
+                    end: ref_token.span(),
+                    kind: ErrorKind::Method(MethodError::ExpectedSelfReference),
+                })
+            }
+        }
+    } else if maybe_self == *"self" {
+        last_span = src.next().unwrap().span();
+        SelfReference::Owned
+    } else {
+        SelfReference::None
+    };
+
+    if self_reference != SelfReference::None {
+        if let Some(comma) = src.peek() {
+            if comma.to_string() != *"," {
+                return Err(Error {
+                    start: last_span,
+                    end: last_span,
+                    kind: ErrorKind::Method(MethodError::NoArgumentComma),
+                });
+            }
+            src.next();
+
+            // trailing comma
+            if src.peek().is_none() {
+                return Ok((self_reference, Vec::new()));
+            }
+        } else {
+            return Ok((self_reference, Vec::new()));
+        }
+    }
+
+    let mut args = Vec::new();
+    loop {
+        let mut consumed = false;
+        if matches!(src.peek(), Some(TokenTree::Punct(hash)) if hash.as_char() == '#') {
+            let hash = src.next().unwrap();
+            let Some(TokenTree::Group(brackets)) = src.next() else {
+                return Err(Error {
+                    start: hash.span(),
+                    end: hash.span(),
+                    kind: ErrorKind::Attribute(AttributeError::NoBrackets),
+                });
+            };
+            match brackets.stream().to_string().as_str() {
+                "consumed" => consumed = true,
+                _ => {
+                    return Err(Error {
+                        start: brackets.span(),
+                        end: brackets.span(),
+                        kind: ErrorKind::Attribute(AttributeError::Unknown),
+                    })
+                }
+            }
+            last_span = brackets.span();
+        }
+
+        let Some(TokenTree::Ident(name)) = src.next() else {
+            return Err(Error {
+                start: last_span,
+                end: last_span,
+                kind: ErrorKind::Method(MethodError::NoArgumentName),
+            });
+        };
+        let Some(TokenTree::Punct(colon)) = src.next() else {
+            return Err(Error {
+                start: name.span(),
+                end: name.span(),
+                kind: ErrorKind::Method(MethodError::NoArgumentColon),
+            });
+        };
+        let ty = crate::parser::parse_type(&mut src, colon.span())?;
+        let ty_span = ty.span();
+
+        if consumed && !matches!(ty, Type::Absolute(_, _)) {
+            return Err(Error {
+                start: name.span(),
+                end: ty_span,
+                kind: ErrorKind::Attribute(AttributeError::WrongTarget(
+                    "arguments that are bound classes",
+                )),
+            });
+        }
+
+        args.push(Argument {
+            name: name.to_string(),
+            ty,
+            consumed,
+            by_ref: false,
+        });
+
+        if src.peek().is_some() {
+            let Some(TokenTree::Punct(comma)) = src.next() else {
+                return Err(Error {
+                    start: name.span(),
+                    end: ty_span,
+                    kind: ErrorKind::Method(MethodError::NoArgumentComma),
+                });
+            };
+            if comma.as_char() != ',' {
+                return Err(Error {
+                    start: name.span(),
+                    end: ty_span,
+                    kind: ErrorKind::Method(MethodError::NoArgumentComma),
+                });
+            }
+
+            // trailing comma
+            if src.peek().is_none() {
+                break;
+            }
+        } else {
+            break;
+        }
+    }
+
+    Ok((self_reference, args))
+}
+
+/// Pairs of method attributes that can't be used together.
+const CONFLICTS: &[(&str, &str)] = &[
+    ("error_param", "variadic"),
+    ("error_param", "singleton"),
+    ("error_param", "out_struct"),
+    ("error_param", "bool_is_success"),
+    ("error_param", "out_params"),
+    ("error_param", "nil_is_err"),
+    ("out_struct", "cf"),
+    ("out_struct", "returns_as"),
+    ("out_struct", "borrowed"),
+    ("out_struct", "nil_is_err"),
+    ("out_struct", "constructor"),
+    ("nullable", "returns_handle"),
+    ("borrowed", "cf"),
+    ("bool_is_success", "returns_as"),
+    ("returns_as", "cf"),
+    ("returns_as", "constructor"),
+    ("out_params", "variadic"),
+    ("out_params", "expose_cmd"),
+    ("out_params", "constructor"),
+    ("constructor", "singleton"),
+    ("constructor", "expose_cmd"),
+    ("constructor", "cf"),
+    ("constructor", "returns_handle"),
+    // Cached strings are never invalidated, so they can't be set.
+    ("cached", "property"),
+];
+
+/// The name of a method attribute that's in [`CONFLICTS`].
+fn attribute_name(attribute: &Attribute) -> Option<&'static str> {
+    let name = match attribute {
+        Attribute::ErrorParam => "error_param",
+        Attribute::Variadic => "variadic",
+        Attribute::Singleton => "singleton",
+        Attribute::OutStruct => "out_struct",
+        Attribute::BoolIsSuccess => "bool_is_success",
+        Attribute::OutParams => "out_params",
+        Attribute::NilIsErr => "nil_is_err",
+        Attribute::Cf(_) => "cf",
+        Attribute::ReturnsAs(_) => "returns_as",
+        Attribute::Borrowed => "borrowed",
+        Attribute::Constructor => "constructor",
+        Attribute::Nullable => "nullable",
+        Attribute::ReturnsHandle(_) => "returns_handle",
+        Attribute::ExposeCmd => "expose_cmd",
+        Attribute::Cached => "cached",
+        Attribute::Property => "property",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// Finds two attributes in `names` that can't be used together, in either order.
+/// Returns their indices, the earlier one first.
+fn find_conflict(names: &[&str]) -> Option<(usize, usize)> {
+    (0..names.len()).find_map(|later| {
+        let earlier = (0..later).find(|&earlier| {
+            CONFLICTS.iter().any(|&(a, b)| {
+                (a, b) == (names[earlier], names[later]) || (b, a) == (names[earlier], names[later])
+            })
+        })?;
+
+        Some((earlier, later))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflicts_are_found_in_either_order() {
+        for (a, b) in CONFLICTS {
+            assert_eq!(find_conflict(&[a, b]), Some((0, 1)));
+            assert_eq!(find_conflict(&[b, a]), Some((0, 1)));
+        }
+        assert_eq!(
+            find_conflict(&["out_struct", "returns_as"]),
+            find_conflict(&["returns_as", "out_struct"])
+        );
+        assert_eq!(
+            find_conflict(&["nullable", "returns_handle"]),
+            find_conflict(&["returns_handle", "nullable"])
+        );
+    }
+
+    #[test]
+    fn conflicts_are_found_between_any_attributes() {
+        // Other attributes in between don't hide the conflict.
+        let names = ["nullable", "throws", "nil_is_err", "returns_handle"];
+        assert_eq!(find_conflict(&names), Some((0, 3)));
+        assert_eq!(find_conflict(&["nullable", "nil_is_err", "throws"]), None);
+        assert_eq!(find_conflict(&[]), None);
+    }
+
+    #[test]
+    fn conflicts_name_real_attributes() {
+        let names: Vec<_> = [
+            Attribute::ErrorParam,
+            Attribute::Variadic,
+            Attribute::Singleton,
+            Attribute::OutStruct,
+            Attribute::BoolIsSuccess,
+            Attribute::OutParams,
+            Attribute::NilIsErr,
+            Attribute::Cf(crate::CfReturn::Get),
+            Attribute::ReturnsAs("u8".into()),
+            Attribute::Borrowed,
+            Attribute::Constructor,
+            Attribute::Nullable,
+            Attribute::ReturnsHandle("Handle".into()),
+            Attribute::ExposeCmd,
+            Attribute::Cached,
+            Attribute::Property,
+        ]
+        .iter()
+        .map(|attribute| attribute_name(attribute).unwrap())
+        .collect();
+
+        for (a, b) in CONFLICTS {
+            assert!(names.contains(a), "`{a}` isn't a method attribute");
+            assert!(names.contains(b), "`{b}` isn't a method attribute");
+        }
+        assert_eq!(attribute_name(&Attribute::Throws), None);
+    }
+
+    #[test]
+    fn selector_needs_a_colon_per_argument() {
+        assert!(check_selector_arity("display", 0).is_ok());
+        assert!(check_selector_arity("initWithFrame:", 1).is_ok());
+        assert!(check_selector_arity("initWithBytes:length:", 2).is_ok());
+        assert!(matches!(
+            check_selector_arity("initWithFrame:", 2),
+            Err(MethodError::SelectorArity { colons: 1, args: 2 })
+        ));
+        assert!(matches!(
+            check_selector_arity("display", 1),
+            Err(MethodError::SelectorArity { colons: 0, args: 1 })
+        ));
+    }
+
+    #[test]
+    fn out_struct_counts_as_an_argument() {
+        let mut func = Function {
+            name: "get_value".into(),
+            return_type: None,
+            args: Vec::new(),
+            self_reference: SelfReference::Immutable,
+            selector: Some("getValue:".into()),
+            cfg: Vec::new(),
+            cf_return: None,
+            expose_cmd: false,
+            handle_return: None,
+            returns_as: None,
+            bool_is_success: false,
+            borrowed_return: false,
+            nullable: false,
+            out_struct: false,
+            throws: false,
+            error_param: false,
+            nil_is_err: false,
+            variadic: false,
+            out_params: false,
+            constructor: false,
+            singleton: false,
+            needs_pool: false,
+            cached: false,
+            body: None,
+            attributes: Vec::new(),
+        };
+        assert_eq!(objc_arg_count(&func), 0);
+        func.out_struct = true;
+        assert_eq!(objc_arg_count(&func), 1);
+    }
+}
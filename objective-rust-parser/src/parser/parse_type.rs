@@ -0,0 +1,223 @@
+use {
+    crate::{Error, ErrorKind, Mutability, Type},
+    proc_macro::{Delimiter, Spacing, Span, TokenStream, TokenTree},
+    std::iter::Peekable,
+};
+
+pub fn parse_type(
+    src: &mut Peekable<impl Iterator<Item = TokenTree>>,
+    start_span: Span,
+) -> Result<Type, Error> {
+    let Some(next) = src.next() else {
+        return Err(Error {
+            start: start_span,
+            end: start_span,
+            kind: ErrorKind::NoType,
+        });
+    };
+    match next {
+        TokenTree::Ident(ty) => {
+            let mut path = ty.to_string();
+
+            // Paths, like `objective_rust::ffi::Class`
+            while let Some(TokenTree::Punct(colon)) = src.peek() {
+                if colon.as_char() != ':' || colon.spacing() != Spacing::Joint {
+                    break;
+                }
+                let colon = src.next().unwrap();
+                let Some(TokenTree::Punct(_)) = src.next() else {
+                    return Err(Error {
+                        start: colon.span(),
+                        end: colon.span(),
+                        kind: ErrorKind::GiveUp,
+                    });
+                };
+                let Some(TokenTree::Ident(segment)) = src.next() else {
+                    return Err(Error {
+                        start: colon.span(),
+                        end: colon.span(),
+                        kind: ErrorKind::NoType,
+                    });
+                };
+
+                path += "::";
+                path += &segment.to_string();
+            }
+
+            // `Option` and `Id` are the only generic types that are supported, since
+            // they're the only ones with a known layout (`Option` only for non-nullable
+            // types). `Block` is supported too, but its closure type is kept as is.
+            if let Some(TokenTree::Punct(open)) = src.peek() {
+                if open.as_char() == '<' && (path == "Block" || path.ends_with("::Block")) {
+                    return parse_block(src, path, ty.span());
+                }
+                if open.as_char() == '<' {
+                    let open = open.span();
+                    let is_option = path == "Option" || path.ends_with("::Option");
+                    let is_id = path == "Id" || path.ends_with("::Id");
+                    if !is_option && !is_id {
+                        return Err(Error {
+                            start: ty.span(),
+                            end: open,
+                            kind: ErrorKind::GiveUp,
+                        });
+                    }
+                    src.next();
+
+                    let inner = parse_type(src, open)?;
+                    let Some(TokenTree::Punct(close)) = src.next() else {
+                        return Err(Error {
+                            start: open,
+                            end: inner.span(),
+                            kind: ErrorKind::GiveUp,
+                        });
+                    };
+                    if close.as_char() != '>' {
+                        return Err(Error {
+                            start: open,
+                            end: close.span(),
+                            kind: ErrorKind::GiveUp,
+                        });
+                    }
+
+                    return Ok(match is_id {
+                        true => Type::Id(path, Box::new(inner), ty.span()),
+                        false => Type::Option(Box::new(inner), ty.span()),
+                    });
+                }
+            }
+
+            Ok(Type::Absolute(path, ty.span()))
+        }
+        TokenTree::Punct(punct) => match punct.as_char() {
+            '*' => {
+                let Some(TokenTree::Ident(const_or_mut)) = src.next() else {
+                    return Err(Error {
+                        start: punct.span(),
+                        end: punct.span(),
+                        kind: ErrorKind::GiveUp,
+                    });
+                };
+                let mutability = match const_or_mut.to_string().as_str() {
+                    "const" => Mutability::Immut,
+                    "mut" => Mutability::Mut,
+                    _ => {
+                        return Err(Error {
+                            start: const_or_mut.span(),
+                            end: const_or_mut.span(),
+                            kind: ErrorKind::GiveUp,
+                        })
+                    }
+                };
+                let other_ty = parse_type(src, const_or_mut.span())?;
+                let other_ty_span = other_ty.span();
+
+                Ok(Type::Pointer(mutability, Box::new(other_ty), other_ty_span))
+            }
+            '&' => {
+                if let Some(TokenTree::Group(brackets)) = src.peek() {
+                    if brackets.delimiter() == Delimiter::Bracket {
+                        let span = brackets.span();
+                        let mut inner = brackets.stream().into_iter().peekable();
+                        let ty = parse_type(&mut inner, brackets.span_open())?;
+                        if let Some(extra) = inner.next() {
+                            return Err(Error {
+                                start: extra.span(),
+                                end: extra.span(),
+                                kind: ErrorKind::GiveUp,
+                            });
+                        }
+                        src.next();
+
+                        return Ok(Type::Slice(Box::new(ty), span));
+                    }
+                }
+
+                let mutability = match src.peek() {
+                    Some(TokenTree::Ident(ident)) if ident.to_string() == "mut" => {
+                        src.next();
+                        Mutability::Mut
+                    }
+                    _ => Mutability::Immut,
+                };
+                let ty = parse_type(src, punct.span())?;
+
+                Ok(Type::Borrow(mutability, Box::new(ty), punct.span()))
+            }
+            _ => Err(Error {
+                start: punct.span(),
+                end: punct.span(),
+                kind: ErrorKind::NoType,
+            }),
+        },
+        TokenTree::Group(group) => {
+            if group.delimiter() != Delimiter::Parenthesis {
+                return Err(Error {
+                    start: group.span_open(),
+                    end: group.span_close(),
+                    kind: ErrorKind::GiveUp,
+                });
+            }
+
+            let mut types = Vec::new();
+            let mut inner = group.stream().into_iter().peekable();
+            while inner.peek().is_some() {
+                types.push(parse_type(&mut inner, group.span_open())?);
+                if inner.peek().is_some() && inner.next().unwrap().to_string() != "," {
+                    return Err(Error {
+                        start: group.span_open(),
+                        end: group.span_close(),
+                        kind: ErrorKind::NoComma,
+                    });
+                }
+            }
+
+            Ok(Type::Tuple(types, group.span()))
+        }
+        _ => Err(Error {
+            start: next.span(),
+            end: next.span(),
+            kind: ErrorKind::NoType,
+        }),
+    }
+}
+
+/// Parses the generics of a `Block` type, starting at the `<`.
+fn parse_block(
+    src: &mut Peekable<impl Iterator<Item = TokenTree>>,
+    path: String,
+    span: Span,
+) -> Result<Type, Error> {
+    let mut generics = Vec::new();
+    let mut depth = 0;
+    let mut after_dash = false;
+
+    loop {
+        let Some(token) = src.next() else {
+            return Err(Error {
+                start: span,
+                end: span,
+                kind: ErrorKind::GiveUp,
+            });
+        };
+        if let TokenTree::Punct(punct) = &token {
+            match punct.as_char() {
+                '<' => depth += 1,
+                // The `>` in `->` doesn't close anything
+                '>' if !after_dash => depth -= 1,
+                _ => {}
+            }
+            after_dash = punct.as_char() == '-';
+        } else {
+            after_dash = false;
+        }
+
+        generics.push(token);
+        if depth == 0 {
+            break;
+        }
+    }
+
+    let generics = TokenStream::from_iter(generics);
+    Ok(Type::Block(format!("{path}{generics}"), span))
+}
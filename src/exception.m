@@ -0,0 +1,17 @@
+// Rust can't catch Objective-C exceptions, so `objective_rust::ffi::catch_exception`
+// makes its call inside an `@try` block here instead.
+
+#include <objc/objc.h>
+
+id objc_retain(id value);
+
+// Calls `func` with `context`. If it throws an exception, returns the exception
+// (retained, so the caller owns it). Otherwise, returns nil.
+id objective_rust_try(void (*func)(void *), void *context) {
+    @try {
+        func(context);
+        return nil;
+    } @catch (id exception) {
+        return objc_retain(exception);
+    }
+}
@@ -1,7 +1,9 @@
 pub use objective_rust_macros::*;
 
-#[cfg(not(target_os = "macos"))]
-compile_error!("objective-rust only supports macOS");
+#[cfg(not(any(target_os = "macos", feature = "stubs")))]
+compile_error!(
+    "objective-rust only supports macOS. Enable the `stubs` feature to build on other platforms."
+);
 
 /// Objective-C's boolean type.
 #[repr(transparent)]
@@ -22,27 +24,594 @@ impl From<bool> for ObjcBool {
     }
 }
 impl From<ObjcBool> for bool {
+    /// `0` is `NO`, and anything else is `YES`, like in C. Where `BOOL` is a `signed
+    /// char` (on Intel), methods can return values other than `1` for `YES`, like a
+    /// flag masked out of a bitfield.
     fn from(value: ObjcBool) -> bool {
-        match value.0 {
-            1 => true,
-            0 => false,
-            _ => unreachable!(),
+        value.0 != 0
+    }
+}
+
+/// Generates a type for an Objective-C option set (a bitmask, like `NSWindowStyleMask`).
+///
+/// Each option becomes an associated constant, and the type implements the bitwise
+/// operators so options can be combined:
+///
+/// ```ignore
+/// objc_options! {
+///     pub NSWindowStyleMask: u64 {
+///         Titled = 1 << 0,
+///         Closable = 1 << 1,
+///     }
+/// }
+///
+/// let mask = NSWindowStyleMask::Titled | NSWindowStyleMask::Closable;
+/// assert!(mask.contains(NSWindowStyleMask::Closable));
+/// ```
+///
+/// The generated type is `#[repr(transparent)]` over the given integer type, so it
+/// can be passed directly to Objective-C methods that expect the option set. That
+/// type has to match the width the option set is declared with: most are
+/// `NSUInteger` (`usize`), but some are fixed-width, like `uint32_t` (`u32`). Using
+/// anything other than a primitive integer is a compile error.
+#[macro_export]
+macro_rules! objc_options {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis $name:ident: $ty:ty {
+            $($(#[$option_attr:meta])* $option:ident = $value:expr),* $(,)?
+        }
+    )*) => {$(
+        $(#[$attr])*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+        #[repr(transparent)]
+        $vis struct $name(pub $ty);
+
+        const _: () = {
+            fn check<T: $crate::OptionBits>() {}
+            let _ = check::<$ty>;
+        };
+        // Option sets are passed as their backing integer.
+        unsafe impl $crate::ffi::Encode for $name {
+            const ENCODING: &'static str = <$ty as $crate::ffi::Encode>::ENCODING;
+        }
+
+        #[allow(non_upper_case_globals, dead_code)]
+        impl $name {
+            $(
+                $(#[$option_attr])*
+                pub const $option: Self = Self($value);
+            )*
+
+            /// An option set with no options enabled.
+            pub const fn empty() -> Self {
+                Self(0)
+            }
+            /// The raw bits of this option set.
+            pub const fn bits(self) -> $ty {
+                self.0
+            }
+            /// Creates an option set from raw bits.
+            pub const fn from_bits(bits: $ty) -> Self {
+                Self(bits)
+            }
+            /// Returns `true` if no options are enabled.
+            pub const fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+            /// Returns `true` if every option in `other` is also enabled in `self`.
+            pub const fn contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+            /// Enables every option in `other`.
+            pub fn insert(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+            /// Disables every option in `other`.
+            pub fn remove(&mut self, other: Self) {
+                self.0 &= !other.0;
+            }
+        }
+        impl core::ops::BitOr for $name {
+            type Output = Self;
+
+            fn bitor(self, rhs: Self) -> Self {
+                Self(self.0 | rhs.0)
+            }
+        }
+        impl core::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.0 |= rhs.0;
+            }
+        }
+        impl core::ops::BitAnd for $name {
+            type Output = Self;
+
+            fn bitand(self, rhs: Self) -> Self {
+                Self(self.0 & rhs.0)
+            }
+        }
+        impl core::ops::BitAndAssign for $name {
+            fn bitand_assign(&mut self, rhs: Self) {
+                self.0 &= rhs.0;
+            }
+        }
+    )*};
+}
+
+/// Generates a type for an Objective-C enum (`NS_ENUM`, like `NSComparisonResult`):
+///
+/// ```ignore
+/// objc_enum! {
+///     pub NSComparisonResult: isize {
+///         OrderedAscending = -1,
+///         OrderedSame = 0,
+///         OrderedDescending = 1,
+///     }
+/// }
+///
+/// assert_eq!(NSComparisonResult::from(-1), NSComparisonResult::OrderedAscending);
+/// ```
+///
+/// Like [`objc_options!`], the generated type is a `#[repr(transparent)]` wrapper
+/// around the enum's integer type, with a constant for each case, instead of a Rust
+/// `enum`. Objective-C can return values that aren't in the enum (like cases added
+/// in newer OS versions), and those would be undefined behaviour in a Rust `enum`;
+/// here they're just another value. The type converts to and from its integer type
+/// with `From`, and implements [`ObjcEnum`], so it can be listed in
+/// `#[objrs(enums(...))]`. Most enums are `NSInteger` (`isize`) or `NSUInteger`
+/// (`usize`).
+#[macro_export]
+macro_rules! objc_enum {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis $name:ident: $ty:ty {
+            $($(#[$case_attr:meta])* $case:ident = $value:expr),* $(,)?
+        }
+    )*) => {$(
+        $(#[$attr])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+        #[repr(transparent)]
+        $vis struct $name(pub $ty);
+
+        const _: () = {
+            fn check<T: $crate::EnumRepr>() {}
+            let _ = check::<$ty>;
+        };
+        // Enums are passed as their backing integer.
+        unsafe impl $crate::ffi::Encode for $name {
+            const ENCODING: &'static str = <$ty as $crate::ffi::Encode>::ENCODING;
+        }
+        unsafe impl $crate::ObjcEnum for $name {
+            type Repr = $ty;
+        }
+
+        #[allow(non_upper_case_globals, dead_code)]
+        impl $name {
+            $(
+                $(#[$case_attr])*
+                pub const $case: Self = Self($value);
+            )*
+        }
+        impl From<$ty> for $name {
+            fn from(value: $ty) -> Self {
+                Self(value)
+            }
+        }
+        impl From<$name> for $ty {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+        // Prints the case's name, or the raw value for values that aren't a case.
+        impl core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                $(
+                    if *self == Self::$case {
+                        return f.write_str(stringify!($case));
+                    }
+                )*
+                f.debug_tuple(stringify!($name)).field(&self.0).finish()
+            }
+        }
+    )*};
+}
+
+/// Creates an [`NSString`](ffi::NSString) with Rust's formatting syntax, like
+/// [`format!`]:
+///
+/// ```ignore
+/// let title = ns_format!("{} items", items.len());
+/// ```
+///
+/// The string is formatted in Rust and then copied into an `NSString`, so this uses
+/// Rust's format specifiers (`{}`, `{:?}`, `{:.2}`), *not* Objective-C's (`%@`, `%d`).
+/// Arguments need to implement Rust's formatting traits, so Objective-C objects have
+/// to be formatted through something like the `description()` method generated by
+/// `#[description]`. In exchange, this avoids calling variadic methods like
+/// `stringWithFormat:`, whose arguments can't be type-checked.
+#[macro_export]
+macro_rules! ns_format {
+    ($($arg:tt)*) => {
+        $crate::ffi::NSString::new(&::std::format!($($arg)*))
+    };
+}
+
+/// Foundation's value types, which are passed to and returned from methods by value.
+pub mod foundation {
+    use std::{cmp::Ordering, ops::Range};
+
+    /// A range of items, like characters in a string or elements in an array.
+    ///
+    /// https://developer.apple.com/documentation/foundation/nsrange?language=objc
+    #[repr(C)]
+    #[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+    pub struct NSRange {
+        /// The index of the first item in the range.
+        pub location: usize,
+        /// The number of items in the range.
+        pub length: usize,
+    }
+    impl NSRange {
+        /// `NSNotFound`, which methods like `rangeOfString:` return as the location
+        /// when nothing was found.
+        pub const NOT_FOUND: usize = isize::MAX as usize;
+
+        pub const fn new(location: usize, length: usize) -> Self {
+            Self { location, length }
+        }
+
+        /// Returns `false` if the range's location is `NSNotFound`.
+        pub const fn is_found(&self) -> bool {
+            self.location != Self::NOT_FOUND
+        }
+
+        /// The index after the last item in the range.
+        pub const fn end(&self) -> usize {
+            self.location + self.length
+        }
+    }
+    impl From<Range<usize>> for NSRange {
+        fn from(range: Range<usize>) -> Self {
+            Self::new(range.start, range.len())
+        }
+    }
+    impl From<NSRange> for Range<usize> {
+        fn from(range: NSRange) -> Self {
+            range.location..range.end()
+        }
+    }
+
+    unsafe impl crate::ffi::Encode for NSRange {
+        const ENCODING: &'static str = "{_NSRange=QQ}";
+    }
+
+    /// The result of comparing two values, returned by methods like `compare:`. Bind
+    /// those methods with `#[returns_as(core::cmp::Ordering)]` to get an [`Ordering`]
+    /// instead.
+    ///
+    /// https://developer.apple.com/documentation/foundation/nscomparisonresult?language=objc
+    #[repr(transparent)]
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+    pub struct NSComparisonResult(pub isize);
+    impl NSComparisonResult {
+        pub const ORDERED_ASCENDING: Self = Self(-1);
+        pub const ORDERED_SAME: Self = Self(0);
+        pub const ORDERED_DESCENDING: Self = Self(1);
+    }
+    impl From<NSComparisonResult> for Ordering {
+        /// `NSOrderedAscending` (the left value is smaller) is [`Ordering::Less`].
+        fn from(result: NSComparisonResult) -> Self {
+            result.0.cmp(&0)
+        }
+    }
+    impl From<Ordering> for NSComparisonResult {
+        fn from(ordering: Ordering) -> Self {
+            Self(ordering as isize)
+        }
+    }
+    unsafe impl crate::ffi::Encode for NSComparisonResult {
+        const ENCODING: &'static str = "q";
+    }
+
+    /// A point in a 2D coordinate system.
+    ///
+    /// https://developer.apple.com/documentation/foundation/nspoint?language=objc
+    #[repr(C)]
+    #[derive(Clone, Copy, Default, PartialEq, Debug)]
+    pub struct NSPoint {
+        pub x: f64,
+        pub y: f64,
+    }
+    unsafe impl crate::ffi::Encode for NSPoint {
+        const ENCODING: &'static str = "{CGPoint=dd}";
+    }
+
+    /// A 2D size.
+    ///
+    /// https://developer.apple.com/documentation/foundation/nssize?language=objc
+    #[repr(C)]
+    #[derive(Clone, Copy, Default, PartialEq, Debug)]
+    pub struct NSSize {
+        pub width: f64,
+        pub height: f64,
+    }
+    unsafe impl crate::ffi::Encode for NSSize {
+        const ENCODING: &'static str = "{CGSize=dd}";
+    }
+
+    /// A rectangle, made of its origin (usually its bottom-left corner) and its size.
+    ///
+    /// https://developer.apple.com/documentation/foundation/nsrect?language=objc
+    #[repr(C)]
+    #[derive(Clone, Copy, Default, PartialEq, Debug)]
+    pub struct NSRect {
+        pub origin: NSPoint,
+        pub size: NSSize,
+    }
+    unsafe impl crate::ffi::Encode for NSRect {
+        const ENCODING: &'static str = "{CGRect={CGPoint=dd}{CGSize=dd}}";
+    }
+}
+
+/// Integer types that can back an option set made with [`objc_options!`].
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't back an option set",
+    label = "option sets must be backed by a primitive integer type"
+)]
+pub trait OptionBits {}
+macro_rules! impl_option_bits {
+    ($($ty:ty),*) => {$(impl OptionBits for $ty {})*};
+}
+impl_option_bits!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Integer types that can back an enum made with [`objc_enum!`].
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't back an enum",
+    label = "enums must be backed by a primitive integer type"
+)]
+pub trait EnumRepr {}
+macro_rules! impl_enum_repr {
+    ($($ty:ty),*) => {$(impl EnumRepr for $ty {})*};
+}
+impl_enum_repr!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// Types that are safe to pass to and return from Objective-C as an enum: any value
+/// of their backing integer type, [`ObjcEnum::Repr`], is a valid value of the type.
+/// [`objc_enum!`] implements this. Types listed in `#[objrs(enums(...))]` are checked
+/// for it at compile time.
+///
+/// # Safety
+/// The type must have the same layout as `Repr`, and every `Repr` must be a valid
+/// value of it. A Rust `enum` only meets this if it has a case for every value of
+/// its `#[repr]` integer.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` isn't an FFI-safe enum",
+    label = "declare the enum with `objc_enum!`"
+)]
+pub unsafe trait ObjcEnum: Copy {
+    /// The integer type the enum is passed as.
+    type Repr;
+}
+
+/// Bindings [`objrs`] has to reject at compile time, as `compile_fail` doctests. Each
+/// is a variation on these bindings, which compile:
+///
+/// ```
+/// use objective_rust::{foundation::NSRect, objrs};
+///
+/// pub struct Handle(core::ptr::NonNull<()>);
+/// pub struct Hash(usize);
+/// impl From<usize> for Hash {
+///     fn from(hash: usize) -> Self {
+///         Self(hash)
+///     }
+/// }
+///
+/// #[objrs]
+/// extern "objc" {
+///     type NSValue;
+///
+///     #[out_struct]
+///     #[selector = "getValue:"]
+///     fn rect_value(&self) -> NSRect;
+///     #[returns_as(Hash)]
+///     #[selector = "hash"]
+///     fn hash_value(&self) -> usize;
+///     #[nullable]
+///     #[selector = "pointerValue"]
+///     fn pointer_value(&self) -> *mut ();
+///     #[returns_handle(Handle)]
+///     #[selector = "pointerValue"]
+///     fn handle_value(&self) -> *mut ();
+///     #[selector = "initWithBytes:objCType:"]
+///     fn init_with_bytes(self, bytes: *const (), ty: *const i8) -> Self;
+/// }
+/// #[objrs]
+/// extern "objc" {
+///     type NSData;
+///
+///     #[selector = "initWithBytes:length:"]
+///     fn init_with_bytes(self, bytes: &[u8]) -> Self;
+/// }
+///
+/// fn main() {}
+/// ```
+#[cfg(doctest)]
+mod rejected_bindings {
+    /// Conflicting attributes are rejected whichever order they're in.
+    ///
+    /// ```compile_fail
+    /// # use objective_rust::{foundation::NSRect, objrs};
+    /// #[objrs]
+    /// extern "objc" {
+    ///     type NSValue;
+    ///
+    ///     #[out_struct]
+    ///     #[returns_as(u8)]
+    ///     #[selector = "getValue:"]
+    ///     fn rect_value(&self) -> NSRect;
+    /// }
+    /// # fn main() {}
+    /// ```
+    ///
+    /// ```compile_fail
+    /// # use objective_rust::{foundation::NSRect, objrs};
+    /// #[objrs]
+    /// extern "objc" {
+    ///     type NSValue;
+    ///
+    ///     #[returns_as(u8)]
+    ///     #[out_struct]
+    ///     #[selector = "getValue:"]
+    ///     fn rect_value(&self) -> NSRect;
+    /// }
+    /// # fn main() {}
+    /// ```
+    ///
+    /// ```compile_fail
+    /// # use objective_rust::objrs;
+    /// # pub struct Handle(core::ptr::NonNull<()>);
+    /// #[objrs]
+    /// extern "objc" {
+    ///     type NSValue;
+    ///
+    ///     #[nullable]
+    ///     #[returns_handle(Handle)]
+    ///     #[selector = "pointerValue"]
+    ///     fn pointer_value(&self) -> *mut ();
+    /// }
+    /// # fn main() {}
+    /// ```
+    ///
+    /// ```compile_fail
+    /// # use objective_rust::objrs;
+    /// # pub struct Handle(core::ptr::NonNull<()>);
+    /// #[objrs]
+    /// extern "objc" {
+    ///     type NSValue;
+    ///
+    ///     #[returns_handle(Handle)]
+    ///     #[nullable]
+    ///     #[selector = "pointerValue"]
+    ///     fn pointer_value(&self) -> *mut ();
+    /// }
+    /// # fn main() {}
+    /// ```
+    struct Conflicts;
+
+    /// An explicit selector needs a `:` per argument.
+    ///
+    /// ```compile_fail
+    /// # use objective_rust::objrs;
+    /// #[objrs]
+    /// extern "objc" {
+    ///     type NSValue;
+    ///
+    ///     #[selector = "initWithBytes:"]
+    ///     fn init_with_bytes(self, bytes: *const (), ty: *const i8) -> Self;
+    /// }
+    /// # fn main() {}
+    /// ```
+    ///
+    /// Slices count as two arguments: their pointer and length.
+    ///
+    /// ```compile_fail
+    /// # use objective_rust::objrs;
+    /// #[objrs]
+    /// extern "objc" {
+    ///     type NSData;
+    ///
+    ///     #[selector = "initWithBytes:"]
+    ///     fn init_with_bytes(self, bytes: &[u8]) -> Self;
+    /// }
+    /// # fn main() {}
+    /// ```
+    struct SelectorArity;
+}
+
+/// Tracing for Objective-C method calls, enabled with the `trace` feature.
+///
+/// When the feature is enabled, every method generated by [`objrs`] reports the call
+/// before dispatching it. By default calls are printed to stderr; use [`set_handler`]
+/// to send them somewhere else (like a logging library). Without the feature, none of
+/// this code is generated.
+#[cfg(feature = "trace")]
+pub mod trace {
+    use std::sync::RwLock;
+
+    /// A method call that's about to be dispatched.
+    #[derive(Debug, Clone, Copy)]
+    pub struct Dispatch {
+        /// The name of the class the method is bound on.
+        pub class: &'static str,
+        /// The selector being sent.
+        pub selector: &'static str,
+        /// If this is a class method (`+`) instead of an instance method (`-`).
+        pub class_method: bool,
+    }
+
+    static HANDLER: RwLock<Option<fn(&Dispatch)>> = RwLock::new(None);
+
+    /// Sets the function that's called for every traced method call.
+    pub fn set_handler(handler: fn(&Dispatch)) {
+        *HANDLER.write().unwrap() = Some(handler);
+    }
+
+    /// Called by generated bindings before a method is dispatched.
+    pub fn dispatch(class: &'static str, selector: &'static str, class_method: bool) {
+        let dispatch = Dispatch {
+            class,
+            selector,
+            class_method,
+        };
+
+        match *HANDLER.read().unwrap() {
+            Some(handler) => handler(&dispatch),
+            None => {
+                let kind = if class_method { '+' } else { '-' };
+                eprintln!("[objective-rust] {kind}[{class} {selector}]");
+            }
         }
     }
 }
 
 pub mod ffi {
-    use std::{ffi::CString, ptr::NonNull};
+    use std::{
+        collections::HashMap,
+        ffi::{CStr, CString},
+        ptr::NonNull,
+        sync::Arc,
+    };
     type Ptr = NonNull<()>;
 
     /// An Objective-C class.
     #[repr(transparent)]
     #[derive(Clone, Copy)]
     pub struct Class(Ptr);
+    impl Class {
+        /// Returns the pointer to the class object.
+        pub fn as_ptr(self) -> *mut () {
+            self.0.as_ptr()
+        }
+    }
     /// An instance of an Objective-C class.
     #[repr(transparent)]
     #[derive(Clone, Copy)]
     pub struct Instance(Ptr);
+    impl Instance {
+        /// Treats a pointer to an object as an [`Instance`].
+        pub fn from_ptr<T>(ptr: NonNull<T>) -> Self {
+            Self(ptr.cast())
+        }
+
+        /// Returns the pointer to the object.
+        pub fn as_ptr(self) -> *mut () {
+            self.0.as_ptr()
+        }
+    }
     /// A pointer to the implementation of an Objective-C function.
     #[repr(transparent)]
     #[derive(Clone, Copy)]
@@ -55,6 +624,21 @@ pub mod ffi {
     #[repr(transparent)]
     #[derive(Clone, Copy)]
     pub struct Method(Ptr);
+    /// An Objective-C protocol, like `NSWindowDelegate`.
+    #[repr(transparent)]
+    #[derive(Clone, Copy)]
+    pub struct Protocol(Ptr);
+
+    // Classes, protocols, selectors, and method implementations are registered once and
+    // never freed, so they can be shared between threads.
+    unsafe impl Send for Class {}
+    unsafe impl Sync for Class {}
+    unsafe impl Send for Protocol {}
+    unsafe impl Sync for Protocol {}
+    unsafe impl Send for Selector {}
+    unsafe impl Sync for Selector {}
+    unsafe impl Send for Implementation {}
+    unsafe impl Sync for Implementation {}
 
     /// Returns a [`Class`] if one exists for `name`. Otherwise returns `None`.
     ///
@@ -73,6 +657,18 @@ pub mod ffi {
         Some(Class(Ptr::new(ptr)?))
     }
 
+    /// Finds a protocol by its name. Returns `None` if no loaded image defines it, which
+    /// happens when nothing compiled into the process uses or adopts the protocol, even
+    /// if it's declared in a framework's headers.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418870-objc_getprotocol?language=objc
+    pub fn get_protocol(name: &str) -> Option<Protocol> {
+        let name = CString::new(name).ok()?;
+        let ptr = unsafe { objc_getProtocol(name.as_ptr()) };
+
+        Some(Protocol(Ptr::new(ptr)?))
+    }
+
     pub fn get_selector(name: &str) -> Option<Selector> {
         let name = CString::new(name).ok()?;
         let ptr = unsafe { sel_getUid(name.as_ptr()) };
@@ -80,17 +676,2371 @@ pub mod ffi {
         Some(Selector(Ptr::new(ptr)?))
     }
 
+    /// Returns `objc_msgSend`, which finds and calls the receiver's implementation of a
+    /// method. Transmute it to the method's signature (`extern "C" fn(receiver,
+    /// selector, args...) -> ret`) to call it.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1456712-objc_msgsend?language=objc
+    #[inline(always)]
+    pub fn get_msg_send() -> Implementation {
+        Implementation(Ptr::new(objc_msgSend as *mut ()).unwrap())
+    }
+
+    /// The receiver of a message sent to a superclass' implementation of a method, with
+    /// [`get_msg_send_super_for`]: the object (or class, for class methods) receiving
+    /// the message, and the class whose implementation is called.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/objc_super?language=objc
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct ObjcSuper {
+        pub receiver: *mut (),
+        pub super_class: Class,
+    }
+
+    /// Returns the `objc_msgSendSuper` variant for calling a method that returns `R`,
+    /// which calls `super_class`' implementation of a method. Transmute it to the
+    /// method's signature, with a `*const ObjcSuper` in place of the receiver. Like
+    /// [`get_msg_send_for`], this picks `objc_msgSendSuper_stret` for large structs.
+    ///
+    /// Methods implemented in Rust get a `super_<name>` function that does this.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1456716-objc_msgsendsuper?language=objc
+    #[inline(always)]
+    pub fn get_msg_send_super_for<R>() -> Implementation {
+        #[cfg(target_arch = "x86_64")]
+        if core::mem::size_of::<R>() > STRET_THRESHOLD {
+            return Implementation(Ptr::new(objc_msgSendSuper_stret as *mut ()).unwrap());
+        }
+
+        Implementation(Ptr::new(objc_msgSendSuper as *mut ()).unwrap())
+    }
+
+    /// The largest return type, in bytes, that `objc_msgSend` can return in registers.
+    /// Methods returning anything larger (like an `NSRect`) have to be called with
+    /// `objc_msgSend_stret` instead, which is passed a pointer to write the return
+    /// value to as a hidden first argument.
+    ///
+    /// This is only the case on x86_64, where structs larger than two registers are
+    /// returned in memory. arm64's `objc_msgSend` handles every return type, so there's
+    /// no limit there.
+    #[cfg(target_arch = "x86_64")]
+    pub const STRET_THRESHOLD: usize = 2 * core::mem::size_of::<usize>();
+    #[cfg(not(target_arch = "x86_64"))]
+    pub const STRET_THRESHOLD: usize = usize::MAX;
+
+    /// Returns the `objc_msgSend` variant for calling a method that returns `R`:
+    /// `objc_msgSend_stret` if `R` is larger than [`STRET_THRESHOLD`], and
+    /// `objc_msgSend` otherwise. Bound methods are called with this.
+    ///
+    /// The hidden return pointer `objc_msgSend_stret` expects is the same one the C ABI
+    /// passes for large return values, so either is transmuted to the method's
+    /// signature (`extern "C" fn(receiver, selector, args...) -> R`) the same way.
+    ///
+    /// Floating-point returns don't need `objc_msgSend_fpret`. On x86_64 it's only for
+    /// `long double`, which is returned on the x87 stack and has no Rust equivalent;
+    /// `f32` and `f64` are returned in `xmm0`, which `objc_msgSend` also zeroes when
+    /// the receiver is nil. (Using `_fpret` for them would return garbage for nil.)
+    /// arm64 doesn't have any variants.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1456730-objc_msgsend_stret?language=objc
+    #[inline(always)]
+    pub fn get_msg_send_for<R>() -> Implementation {
+        #[cfg(target_arch = "x86_64")]
+        if core::mem::size_of::<R>() > STRET_THRESHOLD {
+            return Implementation(Ptr::new(objc_msgSend_stret as *mut ()).unwrap());
+        }
+
+        get_msg_send()
+    }
+
+    /// Returns the implementation of `method` for instances of `class`, without
+    /// going through message dispatch. Calling it directly skips overrides in
+    /// subclasses of `class`, which is what `super` calls need.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418811-class_getmethodimplementation?language=objc
     #[inline(always)]
     pub fn get_method_impl(class: Class, method: Selector) -> Option<Implementation> {
         let ptr = unsafe { class_getMethodImplementation(class, method) };
         Some(Implementation(Ptr::new(ptr)?))
     }
 
-    #[link(name = "objc")]
-    extern "C" {
-        fn class_getMethodImplementation(cls: Class, name: Selector) -> *mut ();
-        fn objc_getClass(name: *const i8) -> *mut ();
-        fn objc_getMetaClass(name: *const i8) -> *mut ();
-        fn sel_getUid(name: *const i8) -> *mut ();
+    /// Checks if instances of `class` respond to `selector`, either by implementing it or
+    /// inheriting it. Pass a metaclass to check for a class method instead.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418956-class_respondstoselector?language=objc
+    pub fn class_responds_to(class: Class, selector: Selector) -> bool {
+        unsafe { class_respondsToSelector(class, selector) }.into()
+    }
+
+    /// Checks if `class` adopts `protocol`. Protocols adopted by a superclass count,
+    /// but (like `conformsToProtocol:`) a class that implements the protocol's methods
+    /// without declaring that it adopts the protocol doesn't conform to it.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418893-class_conformstoprotocol?language=objc
+    pub fn class_conforms_to(class: Class, protocol: Protocol) -> bool {
+        let mut class = Some(class);
+        while let Some(current) = class {
+            if unsafe { class_conformsToProtocol(current, protocol) }.into() {
+                return true;
+            }
+            class = Ptr::new(unsafe { class_getSuperclass(current) }).map(Class);
+        }
+
+        false
+    }
+
+    /// Checks if `instance` responds to `selector`, using the class it actually is (which
+    /// may be a subclass of the class it was bound as).
+    pub fn instance_responds_to(instance: Instance, selector: Selector) -> bool {
+        class_responds_to(get_object_class(instance), selector)
+    }
+
+    /// Returns the class `instance` actually is at runtime, which may be a subclass of
+    /// the class it was bound as (like the concrete subclass a factory method returns).
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418629-object_getclass?language=objc
+    pub fn get_object_class(instance: Instance) -> Class {
+        Class(Ptr::new(unsafe { object_getClass(instance.as_ptr()) }).unwrap())
+    }
+
+    /// Returns the name of a [`Class`], like `NSWindow`.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418635-class_getname?language=objc
+    pub fn class_name(class: Class) -> &'static str {
+        let name = unsafe { CStr::from_ptr(class_getName(class)) };
+        name.to_str().unwrap_or_default()
+    }
+
+    /// Returns every class registered with the runtime, in no particular order. This
+    /// includes classes from every loaded framework (thousands of them, on macOS), and
+    /// classes defined from Rust that have been registered. Use [`class_name`] to get
+    /// their names.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418579-objc_getclasslist?language=objc
+    pub fn class_list() -> Vec<Class> {
+        loop {
+            let count = unsafe { objc_getClassList(core::ptr::null_mut(), 0) };
+            let mut classes = Vec::with_capacity(count.max(0) as usize);
+            let filled = unsafe { objc_getClassList(classes.as_mut_ptr(), count) };
+            // Classes registered between the two calls don't fit in the buffer, so
+            // try again.
+            if filled > count {
+                continue;
+            }
+
+            unsafe { classes.set_len(filled.max(0) as usize) };
+            return classes;
+        }
+    }
+
+    /// Returns `class`' instance method for `selector`, which may be inherited from a
+    /// superclass. Returns `None` if instances of the class don't have the method.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418530-class_getinstancemethod?language=objc
+    pub fn get_instance_method(class: Class, selector: Selector) -> Option<Method> {
+        Some(Method(Ptr::new(unsafe {
+            class_getInstanceMethod(class, selector)
+        })?))
+    }
+
+    /// Returns `class`' class method for `selector`, which may be inherited from a
+    /// superclass. Returns `None` if the class doesn't have the method.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418887-class_getclassmethod?language=objc
+    pub fn get_class_method(class: Class, selector: Selector) -> Option<Method> {
+        Some(Method(Ptr::new(unsafe {
+            class_getClassMethod(class, selector)
+        })?))
+    }
+
+    /// Swaps the implementations of two methods, so calling one runs the other's code.
+    /// This is how methods are swizzled.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418769-method_exchangeimplementations?language=objc
+    ///
+    /// # Safety
+    /// This changes the methods for the whole process, including for code that isn't
+    /// yours and for calls that are already in progress on other threads. The methods
+    /// must have the same signature, and everything that calls them has to be fine with
+    /// the swapped behaviour.
+    pub unsafe fn exchange_implementations(a: Method, b: Method) {
+        method_exchangeImplementations(a, b)
+    }
+
+    /// Reads the instance variable named `name` from `instance`. Returns `None` if the
+    /// instance's class doesn't have an ivar with that name.
+    ///
+    /// The runtime copies the ivar out as a pointer, so `T` has to be pointer-sized,
+    /// like a pointer, `usize`, or `isize`; this panics otherwise.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1441498-object_getinstancevariable?language=objc
+    ///
+    /// # Safety
+    /// `instance` must be a valid object, and the ivar must hold a valid `T`.
+    pub unsafe fn object_get_ivar<T: Copy>(instance: Instance, name: &str) -> Option<T> {
+        assert_pointer_sized::<T>();
+        let name = CString::new(name).ok()?;
+        let mut value: *mut () = core::ptr::null_mut();
+        let ivar = object_getInstanceVariable(instance.as_ptr(), name.as_ptr(), &mut value);
+        if ivar.is_null() {
+            return None;
+        }
+
+        Some(core::mem::transmute_copy(&value))
+    }
+
+    /// Sets the instance variable named `name` on `instance` to `value`. Returns `false`
+    /// if the instance's class doesn't have an ivar with that name.
+    ///
+    /// Like [`object_get_ivar`], `T` has to be pointer-sized. The old value is
+    /// overwritten as is: it isn't released or dropped.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1441485-object_setinstancevariable?language=objc
+    ///
+    /// # Safety
+    /// `instance` must be a valid object, and the ivar must store a `T`.
+    pub unsafe fn object_set_ivar<T: Copy>(instance: Instance, name: &str, value: T) -> bool {
+        assert_pointer_sized::<T>();
+        let Ok(name) = CString::new(name) else {
+            return false;
+        };
+        let value: *mut () = core::mem::transmute_copy(&value);
+
+        !object_setInstanceVariable(instance.as_ptr(), name.as_ptr(), value).is_null()
+    }
+    fn assert_pointer_sized<T>() {
+        assert_eq!(
+            core::mem::size_of::<T>(),
+            core::mem::size_of::<*mut ()>(),
+            "ivars can only be read and written as pointer-sized types"
+        );
+    }
+
+    /// Returns the type encoding of `class`' method for `selector`, like `v24@0:8@16`
+    /// (which returns `void` and takes an object). Pass a metaclass for class methods.
+    /// Returns `None` if the class doesn't have the method.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418488-method_gettypeencoding?language=objc
+    pub fn get_method_encoding(class: Class, selector: Selector) -> Option<&'static str> {
+        let method = get_instance_method(class, selector)?;
+        let encoding = unsafe { method_getTypeEncoding(method) };
+        if encoding.is_null() {
+            return None;
+        }
+
+        unsafe { CStr::from_ptr(encoding) }.to_str().ok()
+    }
+
+    /// Panics if `class`' method for `selector` doesn't have the signature `expected`
+    /// describes. Bound classes check every method with this when their vtable is
+    /// loaded in debug builds, so methods bound with the wrong arguments are caught
+    /// before they're called.
+    ///
+    /// `expected` is a type encoding without offsets, where `?` matches any type.
+    /// Types only have to be the same kind and size to match, so (for example) `usize`
+    /// matches `NSInteger`, and any pointer matches an object. A `void` return matches
+    /// anything but a struct, since it's fine to ignore a value returned in registers.
+    /// Methods the class doesn't have aren't checked.
+    pub fn check_method_encoding(class: Class, selector: Selector, expected: &str) {
+        if !method_encoding_matches(class, selector, expected) {
+            panic!(
+                "`{}`'s method `{}` is bound with the type encoding `{expected}`, but its real encoding is `{}`",
+                class_name(class),
+                get_selector_name(selector),
+                get_method_encoding(class, selector).unwrap_or_default(),
+            );
+        }
+    }
+
+    /// Checks `class`' method for `selector` against `expected` like
+    /// [`check_method_encoding`], but returns `false` instead of panicking if it doesn't
+    /// match. `try_load` uses this. Methods the class doesn't have match anything.
+    pub fn method_encoding_matches(class: Class, selector: Selector, expected: &str) -> bool {
+        let Some(actual) = get_method_encoding(class, selector) else {
+            return true;
+        };
+
+        let expected_types = split_encoding(expected);
+        let actual_types = split_encoding(actual);
+        let ignores_return = |(expected, actual): (&&str, &&str)| {
+            *expected == "v" && !actual.starts_with(['{', '('])
+        };
+        expected_types.len() == actual_types.len()
+            && expected_types
+                .iter()
+                .zip(&actual_types)
+                .enumerate()
+                .all(|(idx, types)| {
+                    (idx == 0 && ignores_return(types)) || encodings_match(types.0, types.1)
+                })
+    }
+
+    /// Splits a method's type encoding into the encodings of its return type and each of
+    /// its arguments, dropping offsets and type qualifiers (like `r` for `const`).
+    fn split_encoding(encoding: &str) -> Vec<&str> {
+        let bytes = encoding.as_bytes();
+        let mut types = Vec::new();
+        let mut idx = 0;
+        while idx < bytes.len() {
+            while idx < bytes.len() && b"rnNoORV".contains(&bytes[idx]) {
+                idx += 1;
+            }
+            let start = idx;
+            idx = skip_type(bytes, idx);
+            if idx > start {
+                types.push(&encoding[start..idx]);
+            }
+            while idx < bytes.len() && (bytes[idx].is_ascii_digit() || bytes[idx] == b'-') {
+                idx += 1;
+            }
+        }
+
+        types
+    }
+
+    /// Returns the index just after the type encoding starting at `idx`.
+    fn skip_type(bytes: &[u8], idx: usize) -> usize {
+        let Some(&first) = bytes.get(idx) else {
+            return idx;
+        };
+        match first {
+            b'^' => skip_type(bytes, idx + 1),
+            b'@' => match bytes.get(idx + 1) {
+                // Blocks
+                Some(b'?') => idx + 2,
+                // Objects with their class name, like `@"NSString"`
+                Some(b'"') => bytes[idx + 2..]
+                    .iter()
+                    .position(|&byte| byte == b'"')
+                    .map_or(bytes.len(), |end| idx + end + 3),
+                _ => idx + 1,
+            },
+            b'{' | b'(' | b'[' => {
+                let close = match first {
+                    b'{' => b'}',
+                    b'(' => b')',
+                    _ => b']',
+                };
+                let mut depth = 0;
+                for (offset, &byte) in bytes[idx..].iter().enumerate() {
+                    if byte == first {
+                        depth += 1;
+                    } else if byte == close {
+                        depth -= 1;
+                        if depth == 0 {
+                            return idx + offset + 1;
+                        }
+                    }
+                }
+
+                bytes.len()
+            }
+            // Bitfields store their width
+            b'b' => {
+                let digits = bytes[idx + 1..]
+                    .iter()
+                    .take_while(|byte| byte.is_ascii_digit())
+                    .count();
+                idx + 1 + digits
+            }
+            _ => idx + 1,
+        }
+    }
+
+    /// Checks if two type encodings are compatible: they're the same kind of type, and
+    /// the same size.
+    fn encodings_match(expected: &str, actual: &str) -> bool {
+        /// What matters about a type when it's passed or returned.
+        #[derive(PartialEq)]
+        enum Kind<'a> {
+            Void,
+            Pointer,
+            Int(usize),
+            Float(usize),
+            // Stores the struct's name
+            Struct(&'a str),
+            Other(&'a str),
+        }
+        fn kind(encoding: &str) -> Kind<'_> {
+            match encoding.as_bytes()[0] {
+                b'v' => Kind::Void,
+                b'^' | b'@' | b'#' | b':' | b'*' => Kind::Pointer,
+                b'c' | b'C' | b'B' => Kind::Int(1),
+                b's' | b'S' => Kind::Int(2),
+                b'i' | b'I' | b'l' | b'L' => Kind::Int(4),
+                b'q' | b'Q' => Kind::Int(8),
+                b't' | b'T' => Kind::Int(16),
+                b'f' => Kind::Float(4),
+                b'd' => Kind::Float(8),
+                b'{' => {
+                    let end = encoding.find(['=', '}']).unwrap_or(encoding.len());
+                    Kind::Struct(&encoding[1..end])
+                }
+                _ => Kind::Other(encoding),
+            }
+        }
+
+        expected == "?" || kind(expected) == kind(actual)
+    }
+
+    /// Implemented for every class bound with [`objrs`](crate::objrs), so code can be
+    /// generic over bound classes (like `try_cast`, which casts to any of them).
+    ///
+    /// # Safety
+    /// `Self` must be a `#[repr(transparent)]` wrapper that owns a reference to an
+    /// instance of the class [`BoundClass::get_objc_class`] returns.
+    pub unsafe trait BoundClass: Sized {
+        /// The opaque type for instances of the class.
+        type Instance;
+
+        /// Returns the Objective-C class this type binds to.
+        fn get_objc_class() -> Class;
+
+        /// Wraps an instance pointer, taking over the reference it owns.
+        ///
+        /// # Safety
+        /// The pointer must point to a valid instance of the class, and own a reference
+        /// to it.
+        unsafe fn from_raw(ptr: NonNull<Self::Instance>) -> Self;
+    }
+
+    /// A borrowed argument to a bound method, as a pointer, on its way to being passed
+    /// to Objective-C. Borrows of bound classes are passed as the instance pointer
+    /// (with [`BorrowedClass`]), and borrows of anything else as a pointer to the
+    /// value (with [`BorrowedValue`]).
+    ///
+    /// Bindings call `(&BorrowedArg(ptr)).as_arg()` with both traits imported. If `T`
+    /// is a bound class, `BorrowedClass` applies without auto-referencing the receiver,
+    /// so it's picked over `BorrowedValue`, which is implemented for `&BorrowedArg`.
+    /// This only works because `T` is a concrete type in the binding.
+    #[doc(hidden)]
+    pub struct BorrowedArg<T: ?Sized>(pub *mut T);
+    #[doc(hidden)]
+    pub trait BorrowedClass {
+        fn as_arg(&self) -> *mut ();
+    }
+    impl<T: BoundClass> BorrowedClass for BorrowedArg<T> {
+        fn as_arg(&self) -> *mut () {
+            // Bound classes are `#[repr(transparent)]` wrappers around the instance
+            // pointer.
+            unsafe { *self.0.cast::<*mut ()>() }
+        }
+    }
+    #[doc(hidden)]
+    pub trait BorrowedValue {
+        fn as_arg(&self) -> *mut ();
+    }
+    impl<T: ?Sized> BorrowedValue for &BorrowedArg<T> {
+        fn as_arg(&self) -> *mut () {
+            self.0.cast()
+        }
+    }
+
+    /// A new Objective-C class that's being defined at runtime. Classes declared with a
+    /// superclass in an `extern "objc"` block (`type MyClass: NSObject;`) are defined
+    /// with this the first time they're used.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418559-objc_allocateclasspair?language=objc
+    pub struct ClassBuilder(Class);
+    impl ClassBuilder {
+        /// Starts defining a class named `name`, which inherits from `superclass`.
+        /// Returns `None` if a class with that name already exists.
+        pub fn new(name: &str, superclass: Class) -> Option<Self> {
+            let name = CString::new(name).ok()?;
+            let ptr = unsafe { objc_allocateClassPair(superclass, name.as_ptr(), 0) };
+
+            Some(Self(Class(Ptr::new(ptr)?)))
+        }
+
+        /// Adds an instance method, which overrides any method the superclass has for
+        /// `selector`. Returns `false` if this class already has a method for `selector`.
+        ///
+        /// `types` is the method's type encoding: its return type's encoding, `@:` for
+        /// the receiver and selector, then each argument's encoding (see [`Encode`]).
+        ///
+        /// https://developer.apple.com/documentation/objectivec/1418901-class_addmethod?language=objc
+        ///
+        /// # Safety
+        /// `imp` must be an `extern "C" fn(receiver, Selector, args...) -> ret`, whose
+        /// arguments and return type match `types`, and the signature any superclass
+        /// (or the caller) expects the method to have.
+        pub unsafe fn add_method(
+            &mut self,
+            selector: Selector,
+            imp: *const (),
+            types: &str,
+        ) -> bool {
+            add_method(self.0, selector, imp, types)
+        }
+
+        /// Adds a class method. This works like [`ClassBuilder::add_method`], but the
+        /// method is added to the class' metaclass.
+        ///
+        /// # Safety
+        /// The same as [`ClassBuilder::add_method`]; the receiver is the class.
+        pub unsafe fn add_class_method(
+            &mut self,
+            selector: Selector,
+            imp: *const (),
+            types: &str,
+        ) -> bool {
+            let metaclass = Class(Ptr::new(object_getClass(self.0 .0.as_ptr())).unwrap());
+            add_method(metaclass, selector, imp, types)
+        }
+
+        /// Declares that the class adopts `protocol`, so it conforms to it (see
+        /// [`class_conforms_to`]). The protocol's methods still have to be added. Returns
+        /// `false` if the class already adopts the protocol.
+        ///
+        /// https://developer.apple.com/documentation/objectivec/1418773-class_addprotocol?language=objc
+        pub fn add_protocol(&mut self, protocol: Protocol) -> bool {
+            unsafe { class_addProtocol(self.0, protocol) }.into()
+        }
+
+        /// Adds an instance variable named `name` that stores a `T`. Returns `false` if the
+        /// class already has an ivar with that name.
+        ///
+        /// Ivars can only be added before the class is registered. The runtime picks
+        /// their offsets when it is: each one is placed after the superclass' ivars (and
+        /// the ones added before it), at the next offset that's aligned for `T`. Ivars
+        /// start zeroed, and [`object_get_ivar`] and [`object_set_ivar`] can only access
+        /// pointer-sized ones.
+        ///
+        /// https://developer.apple.com/documentation/objectivec/1418756-class_addivar?language=objc
+        pub fn add_ivar<T: Encode>(&mut self, name: &str) -> bool {
+            let Ok(name) = CString::new(name) else {
+                return false;
+            };
+            let types = CString::new(T::ENCODING).unwrap();
+            // The runtime takes the alignment as a power of two.
+            let alignment = core::mem::align_of::<T>().trailing_zeros() as u8;
+
+            unsafe {
+                class_addIvar(
+                    self.0,
+                    name.as_ptr(),
+                    core::mem::size_of::<T>(),
+                    alignment,
+                    types.as_ptr(),
+                )
+            }
+            .into()
+        }
+
+        /// Finishes defining the class, so it can be used. Returns the new class.
+        ///
+        /// https://developer.apple.com/documentation/objectivec/1418603-objc_registerclasspair?language=objc
+        pub fn register(self) -> Class {
+            unsafe { objc_registerClassPair(self.0) };
+            self.0
+        }
+    }
+    unsafe fn add_method(class: Class, selector: Selector, imp: *const (), types: &str) -> bool {
+        let types = CString::new(types).unwrap();
+        class_addMethod(class, selector, imp, types.as_ptr()).into()
+    }
+
+    /// Objective-C's `NSInteger`, a signed pointer-sized integer.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/nsinteger?language=objc
+    pub type NSInteger = isize;
+    /// Objective-C's `NSUInteger`, an unsigned pointer-sized integer.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/nsuinteger?language=objc
+    pub type NSUInteger = usize;
+    /// CoreGraphics' `CGFloat`, which is a `double` on 64-bit targets and a `float` on
+    /// 32-bit ones.
+    ///
+    /// https://developer.apple.com/documentation/corefoundation/cgfloat?language=objc
+    #[cfg(target_pointer_width = "64")]
+    pub type CGFloat = f64;
+    /// CoreGraphics' `CGFloat`, which is a `double` on 64-bit targets and a `float` on
+    /// 32-bit ones.
+    ///
+    /// https://developer.apple.com/documentation/corefoundation/cgfloat?language=objc
+    #[cfg(not(target_pointer_width = "64"))]
+    pub type CGFloat = f32;
+
+    /// Types with an Objective-C type encoding, like `i` for `int` or `@` for objects.
+    /// The runtime stores the encodings of every method's arguments and return type,
+    /// which code that calls methods generically (like `NSInvocation`) relies on, so
+    /// methods implemented in Rust can only use types that implement this.
+    ///
+    /// Pointers are all encoded as `void *` (`^v`), except pointers to `Self` in
+    /// methods implemented in Rust, which are encoded as objects.
+    ///
+    /// https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/ObjCRuntimeGuide/Articles/ocrtTypeEncodings.html
+    ///
+    /// # Safety
+    /// `ENCODING` must describe the type's real layout.
+    #[diagnostic::on_unimplemented(
+        message = "`{Self}` doesn't have an Objective-C type encoding",
+        label = "methods implemented in Rust can only use types that implement `Encode`"
+    )]
+    pub unsafe trait Encode {
+        const ENCODING: &'static str;
+    }
+    macro_rules! impl_encode {
+        ($($ty:ty => $encoding:literal),*) => {
+            $(unsafe impl Encode for $ty {
+                const ENCODING: &'static str = $encoding;
+            })*
+        };
+    }
+    impl_encode!(
+        () => "v",
+        i8 => "c", u8 => "C", i16 => "s", u16 => "S", i32 => "i", u32 => "I",
+        i64 => "q", u64 => "Q", i128 => "t", u128 => "T", isize => "q", usize => "Q",
+        f32 => "f", f64 => "d",
+        Class => "#", Instance => "@", Selector => ":"
+    );
+    // `BOOL` is a `bool` on ARM, and a `signed char` on Intel.
+    #[cfg(target_arch = "aarch64")]
+    impl_encode!(bool => "B", crate::ObjcBool => "B");
+    #[cfg(not(target_arch = "aarch64"))]
+    impl_encode!(bool => "c", crate::ObjcBool => "c");
+    unsafe impl<T> Encode for *const T {
+        const ENCODING: &'static str = "^v";
+    }
+    unsafe impl<T> Encode for *mut T {
+        const ENCODING: &'static str = "^v";
+    }
+
+    /// Retains an Objective-C object, incrementing its retain count. Returns the same
+    /// pointer, for convenience.
+    ///
+    /// Like [`release`], this is a no-op for tagged pointers.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418956-nsobject/1571946-retain?language=objc
+    ///
+    /// # Safety
+    /// `instance` must point to a valid Objective-C object. The caller owns the new
+    /// reference, and has to release it.
+    #[inline(always)]
+    pub unsafe fn retain<T>(instance: NonNull<T>) -> NonNull<T> {
+        objc_retain(instance.as_ptr().cast());
+        instance
+    }
+
+    /// Releases an Objective-C object, decrementing its retain count.
+    ///
+    /// This is a no-op for tagged pointers (small objects, like some `NSNumber`s
+    /// and `NSString`s, that are stored in the pointer itself instead of on the
+    /// heap), so it's safe to call on any object pointer.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418956-objc_release?language=objc
+    ///
+    /// # Safety
+    /// `instance` must point to a valid Objective-C object, and the caller must own
+    /// the reference being released.
+    #[inline(always)]
+    pub unsafe fn release<T>(instance: NonNull<T>) {
+        objc_release(instance.as_ptr().cast());
+    }
+
+    /// A Rust closure wrapped in an Objective-C block, for methods that take one (like
+    /// `enumerateObjectsUsingBlock:`). The closure's signature is the type parameter:
+    /// `Block<dyn Fn(*mut (), usize, *mut ObjcBool)>` is a block that takes an object,
+    /// an index, and a `BOOL *`, and returns nothing. Blocks with up to 6 arguments
+    /// can be made.
+    ///
+    /// Arguments declared as `Block<...>` in a binding are taken by reference, and the
+    /// block's address is passed to Objective-C. Use `dyn Fn(...) + '_` in the binding
+    /// to accept closures that borrow local variables.
+    ///
+    /// This is a stack block: it lives as long as the `Block` does. Methods that store
+    /// the block for later (like completion handlers, and block properties) copy it to
+    /// the heap with `_Block_copy`, and the copy shares the closure, which is dropped
+    /// once the `Block` and every copy of it are gone. The closure must be `'static` for
+    /// those methods (bind them with `dyn Fn(...)`, not `dyn Fn(...) + '_`).
+    ///
+    /// The closure is called on whichever thread the method calls the block on, and
+    /// the last copy can be released (dropping the closure) on any thread. Bindings for
+    /// methods that store the block, or call it on another thread (like completion
+    /// handlers, and `NSOperation`s), must use `dyn Fn(...) + Send`, or
+    /// `dyn Fn(...) + Send + Sync` if the block can be called on several threads at
+    /// once, so closures that aren't thread-safe are rejected. Only use a plain
+    /// `dyn Fn(...)` for blocks that are always called and released on the current
+    /// thread.
+    ///
+    /// [`Block::copy`] makes a heap copy from Rust. Panics in the closure can't unwind
+    /// into Objective-C, so they abort the process.
+    ///
+    /// https://clang.llvm.org/docs/Block-ABI-Apple.html
+    #[repr(C)]
+    pub struct Block<F: ?Sized> {
+        isa: *const (),
+        flags: i32,
+        reserved: i32,
+        invoke: *const (),
+        descriptor: &'static BlockDescriptor,
+        func: Arc<F>,
+    }
+    #[repr(C)]
+    struct BlockDescriptor {
+        reserved: usize,
+        size: usize,
+        copy: unsafe extern "C" fn(dst: *mut (), src: *const ()),
+        dispose: unsafe extern "C" fn(block: *mut ()),
+    }
+    /// The block's descriptor has `copy` and `dispose` helpers.
+    const BLOCK_HAS_COPY_DISPOSE: i32 = 1 << 25;
+    impl<F: ?Sized> Block<F> {
+        const DESCRIPTOR: BlockDescriptor = BlockDescriptor {
+            reserved: 0,
+            size: core::mem::size_of::<Self>(),
+            copy: Self::copy_helper,
+            dispose: Self::dispose_helper,
+        };
+
+        /// Called by `_Block_copy` after it copies the block's bytes to the heap. The
+        /// copy shares the closure, so this takes another reference to it.
+        unsafe extern "C" fn copy_helper(_dst: *mut (), src: *const ()) {
+            let src = &*src.cast::<Self>();
+            core::mem::forget(src.func.clone());
+        }
+        /// Called when a heap copy of the block is freed, to drop its reference to the
+        /// closure.
+        unsafe extern "C" fn dispose_helper(block: *mut ()) {
+            core::ptr::drop_in_place(core::ptr::addr_of_mut!((*block.cast::<Self>()).func));
+        }
+
+        /// Returns a pointer to the block, which can be passed to methods that take a
+        /// block. It's valid for as long as `self` is.
+        pub fn as_ptr(&self) -> *const () {
+            (self as *const Self).cast()
+        }
+
+        /// Wraps `func` in a block.
+        pub fn new(func: impl BlockFn<F>) -> Self {
+            let (func, invoke) = func.into_block_parts();
+
+            Self {
+                isa: stack_block_isa(),
+                flags: BLOCK_HAS_COPY_DISPOSE,
+                reserved: 0,
+                invoke,
+                descriptor: &Self::DESCRIPTOR,
+                func: Arc::from(func),
+            }
+        }
+
+        /// Copies the block to the heap with `_Block_copy`, for storing it (or passing
+        /// it to Objective-C code that doesn't copy blocks itself). The copy can outlive
+        /// `self`.
+        pub fn copy(&self) -> HeapBlock<F>
+        where
+            F: 'static,
+        {
+            let ptr = unsafe { _Block_copy(self.as_ptr()) };
+            HeapBlock {
+                ptr: NonNull::new(ptr.cast_mut()).unwrap(),
+                _func: core::marker::PhantomData,
+            }
+        }
+    }
+
+    /// A block copied to the heap with [`Block::copy`]. It's released with
+    /// `_Block_release` when dropped; Objective-C code holding a copy of it keeps it
+    /// alive.
+    pub struct HeapBlock<F: ?Sized> {
+        ptr: NonNull<()>,
+        _func: core::marker::PhantomData<Arc<F>>,
+    }
+    impl<F: ?Sized> HeapBlock<F> {
+        /// Returns a pointer to the block, which can be passed to methods that take a
+        /// block. It's valid for as long as `self` is.
+        pub fn as_ptr(&self) -> *const () {
+            self.ptr.as_ptr()
+        }
+    }
+    impl<F: ?Sized> Clone for HeapBlock<F> {
+        /// Copying a heap block just retains it.
+        fn clone(&self) -> Self {
+            let ptr = unsafe { _Block_copy(self.as_ptr()) };
+            Self {
+                ptr: NonNull::new(ptr.cast_mut()).unwrap(),
+                _func: core::marker::PhantomData,
+            }
+        }
+    }
+    impl<F: ?Sized> Drop for HeapBlock<F> {
+        fn drop(&mut self) {
+            unsafe { _Block_release(self.as_ptr()) }
+        }
+    }
+
+    /// Closures that can be wrapped in a [`Block<F>`]. This is implemented for closures
+    /// with up to 6 arguments, for the matching `F = dyn Fn(...) -> R`, and for
+    /// `dyn Fn(...) -> R + Send` and `dyn Fn(...) -> R + Send + Sync` when the closure
+    /// is:
+    ///
+    /// ```compile_fail
+    /// # use objective_rust::ffi::Block;
+    /// let count = std::rc::Rc::new(std::cell::Cell::new(0));
+    /// let block = Block::<dyn Fn() + Send>::new(move || count.set(count.get() + 1));
+    /// ```
+    pub trait BlockFn<F: ?Sized> {
+        /// Boxes the closure, and returns the function the block is invoked with.
+        fn into_block_parts(self) -> (Box<F>, *const ());
+    }
+    macro_rules! impl_block_fn {
+        ($($arg:ident: $ty:ident),*) => {
+            impl_block_fn!(@impl [$($arg: $ty),*] []);
+            impl_block_fn!(@impl [$($arg: $ty),*] [+ Send]);
+            impl_block_fn!(@impl [$($arg: $ty),*] [+ Send + Sync]);
+        };
+        (@impl [$($arg:ident: $ty:ident),*] [$($bounds:tt)*]) => {
+            impl<'a, $($ty,)* R, T: Fn($($ty),*) -> R $($bounds)* + 'a>
+                BlockFn<dyn Fn($($ty),*) -> R $($bounds)* + 'a> for T
+            {
+                fn into_block_parts(
+                    self,
+                ) -> (Box<dyn Fn($($ty),*) -> R $($bounds)* + 'a>, *const ()) {
+                    extern "C" fn invoke<$($ty,)* R>(
+                        block: &Block<dyn Fn($($ty),*) -> R $($bounds)* + '_>
+                        $(, $arg: $ty)*
+                    ) -> R {
+                        (block.func)($($arg),*)
+                    }
+
+                    (Box::new(self), invoke::<$($ty,)* R> as *const ())
+                }
+            }
+        };
+    }
+    impl_block_fn!();
+    impl_block_fn!(a: A);
+    impl_block_fn!(a: A, b: B);
+    impl_block_fn!(a: A, b: B, c: C);
+    impl_block_fn!(a: A, b: B, c: C, d: D);
+    impl_block_fn!(a: A, b: B, c: C, d: D, e: E);
+    impl_block_fn!(a: A, b: B, c: C, d: D, e: E, f: F);
+
+    /// `_NSConcreteStackBlock`, the class of blocks that live on the stack.
+    fn stack_block_isa() -> *const () {
+        #[cfg(target_os = "macos")]
+        {
+            extern "C" {
+                static _NSConcreteStackBlock: [*const (); 32];
+            }
+            unsafe { core::ptr::addr_of!(_NSConcreteStackBlock).cast() }
+        }
+        #[cfg(not(target_os = "macos"))]
+        {
+            core::ptr::null()
+        }
+    }
+
+    /// An autorelease pool, like an `@autoreleasepool` block. Objects that are
+    /// autoreleased while it's alive (like the ones most factory methods return) are
+    /// released when it's dropped. Without a pool, they're never released.
+    ///
+    /// Pools are per-thread, and have to be dropped in the reverse order they were
+    /// created in, so `AutoreleasePool` can't be sent to other threads. Make sure it
+    /// outlives everything borrowed from objects in the pool, too: autoreleased objects
+    /// that weren't retained are freed with it.
+    ///
+    /// https://developer.apple.com/documentation/foundation/nsautoreleasepool?language=objc
+    pub struct AutoreleasePool {
+        context: *mut (),
+        _not_send: core::marker::PhantomData<*mut ()>,
+    }
+    impl AutoreleasePool {
+        /// Pushes a new autorelease pool onto the current thread's stack of pools.
+        #[allow(clippy::new_without_default)]
+        pub fn new() -> Self {
+            POOL_DEPTH.with(|depth| depth.set(depth.get() + 1));
+            Self {
+                context: unsafe { objc_autoreleasePoolPush() },
+                _not_send: core::marker::PhantomData,
+            }
+        }
+    }
+    impl Drop for AutoreleasePool {
+        fn drop(&mut self) {
+            unsafe { objc_autoreleasePoolPop(self.context) }
+            POOL_DEPTH.with(|depth| depth.set(depth.get() - 1));
+        }
+    }
+
+    thread_local! {
+        /// How many [`AutoreleasePool`]s are alive on this thread.
+        static POOL_DEPTH: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+    }
+    /// If an [`AutoreleasePool`] is alive on the current thread. Methods marked
+    /// `#[needs_pool]` assert this in debug builds.
+    ///
+    /// Only pools made with [`AutoreleasePool`] (or [`autoreleasepool`]) are counted,
+    /// not ones pushed by Objective-C code, like the pool AppKit's run loop drains
+    /// after each event.
+    pub fn autorelease_pool_active() -> bool {
+        POOL_DEPTH.with(|depth| depth.get() > 0)
+    }
+
+    /// Calls `func` inside a new [`AutoreleasePool`], like an `@autoreleasepool`
+    /// block. Objects autoreleased in `func` are released when it returns.
+    pub fn autoreleasepool<R>(func: impl FnOnce() -> R) -> R {
+        let _pool = AutoreleasePool::new();
+        func()
+    }
+
+    /// An owned reference to an Objective-C object, like a strong reference under ARC.
+    /// Cloning it retains the object, and dropping it releases the object.
+    ///
+    /// Bound methods can return `Id<T>` (or `Option<Id<T>>`, if they can return nil)
+    /// for methods that give the caller ownership of the object, like `new` and `copy`.
+    /// Bound classes convert to and from `Id<{class}Instance>` with `From`.
+    #[repr(transparent)]
+    pub struct Id<T>(NonNull<T>);
+    impl<T> Id<T> {
+        /// Takes ownership of a reference to an object.
+        ///
+        /// # Safety
+        /// `ptr` must point to a valid Objective-C object, and the caller must own the
+        /// reference being passed in (it'll be released when the `Id` is dropped).
+        pub unsafe fn from_raw(ptr: NonNull<T>) -> Self {
+            Self(ptr)
+        }
+
+        /// Retains an object, and returns an owned reference to it.
+        ///
+        /// # Safety
+        /// `ptr` must point to a valid Objective-C object.
+        pub unsafe fn retain(ptr: NonNull<T>) -> Self {
+            Self(retain(ptr))
+        }
+
+        /// Returns the pointer to the object. The reference is still owned by `self`.
+        pub fn as_ptr(&self) -> NonNull<T> {
+            self.0
+        }
+
+        /// Gives up ownership of the object, returning its pointer. The caller is
+        /// responsible for releasing it.
+        pub fn into_raw(self) -> NonNull<T> {
+            core::mem::ManuallyDrop::new(self).0
+        }
+    }
+    impl<T> Clone for Id<T> {
+        fn clone(&self) -> Self {
+            unsafe { Self::retain(self.0) }
+        }
+    }
+    impl<T> Drop for Id<T> {
+        fn drop(&mut self) {
+            unsafe { release(self.0) }
+        }
+    }
+    impl<T> core::fmt::Debug for Id<T> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_tuple("Id").field(&self.0).finish()
+        }
+    }
+
+    /// A weak reference to an Objective-C object, like a `weak` property under ARC. It
+    /// doesn't keep the object alive, and [`WeakId::upgrade`] returns `None` once the
+    /// object is deallocated. Delegates and other back-references should use this, so
+    /// they don't form a retain cycle.
+    ///
+    /// Not every object supports weak references: some classes opt out (with
+    /// `allowsWeakReference`), and the runtime aborts the process if you try to make a
+    /// weak reference to one of them.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418597-objc_storeweak?language=objc
+    pub struct WeakId<T> {
+        // The runtime keeps track of weak references by their address, so the slot is
+        // boxed to keep it in one place when the `WeakId` moves.
+        slot: Box<core::cell::UnsafeCell<*mut ()>>,
+        _object: core::marker::PhantomData<T>,
+    }
+    impl<T> WeakId<T> {
+        /// Makes a weak reference to `object`.
+        pub fn new(object: &Id<T>) -> Self {
+            let slot = Box::new(core::cell::UnsafeCell::new(core::ptr::null_mut()));
+            unsafe { objc_storeWeak(slot.get(), object.as_ptr().as_ptr().cast()) };
+
+            Self {
+                slot,
+                _object: core::marker::PhantomData,
+            }
+        }
+
+        /// Returns a strong reference to the object, or `None` if it's been
+        /// deallocated.
+        pub fn upgrade(&self) -> Option<Id<T>> {
+            let ptr = unsafe { objc_loadWeakRetained(self.slot.get()) };
+            Some(unsafe { Id::from_raw(NonNull::new(ptr)?.cast()) })
+        }
+    }
+    impl<T> Clone for WeakId<T> {
+        fn clone(&self) -> Self {
+            let slot = Box::new(core::cell::UnsafeCell::new(core::ptr::null_mut()));
+            unsafe { objc_copyWeak(slot.get(), self.slot.get()) };
+
+            Self {
+                slot,
+                _object: core::marker::PhantomData,
+            }
+        }
+    }
+    impl<T> Drop for WeakId<T> {
+        fn drop(&mut self) {
+            unsafe { objc_destroyWeak(self.slot.get()) }
+        }
+    }
+    impl<T> core::fmt::Debug for WeakId<T> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("WeakId").finish_non_exhaustive()
+        }
+    }
+
+    /// `NSUTF8StringEncoding`
+    const UTF8: usize = 4;
+
+    /// Returns the UTF-8 bytes of an `NSString`. They're in a buffer that's
+    /// autoreleased, or owned by the string, so they can't outlive either.
+    ///
+    /// `UTF8String` returns a C string, which would end at the first NUL, so the length
+    /// comes from `lengthOfBytesUsingEncoding:` instead.
+    unsafe fn utf8_bytes<'a>(string: NonNull<()>) -> &'a [u8] {
+        let string = Instance::from_ptr(string);
+        let utf8: *const u8 = msg_send(string, get_selector("UTF8String").unwrap(), ());
+        if utf8.is_null() {
+            return &[];
+        }
+        let len: usize = msg_send(
+            string,
+            get_selector("lengthOfBytesUsingEncoding:").unwrap(),
+            (UTF8,),
+        );
+
+        std::slice::from_raw_parts(utf8, len)
+    }
+
+    /// Copies an `NSString` into a Rust [`String`]. NULs in the string are kept, and
+    /// anything that isn't valid UTF-8 is replaced with `U+FFFD`.
+    ///
+    /// # Safety
+    /// `string` must point to a valid `NSString`.
+    pub unsafe fn string_from_nsstring<T>(string: NonNull<T>) -> String {
+        String::from_utf8_lossy(utf8_bytes(string.cast())).into_owned()
+    }
+
+    /// Copies `string` into a new `NSString`, returning a pointer to it. The caller
+    /// owns the string, and has to release it. This is [`NSString::new`] for code that
+    /// works with raw pointers.
+    pub fn nsstring_from_str<T>(string: &str) -> NonNull<T> {
+        NSString::new(string).into_raw()
+    }
+
+    /// Calls a method that takes no arguments and returns an `NSString`, like
+    /// `description`, and copies the string into a [`String`]. Returns an empty string
+    /// if the method returned nil.
+    ///
+    /// # Safety
+    /// - `instance` must point to a valid Objective-C object.
+    /// - The method for `selector` must take no arguments and return an `NSString`.
+    pub unsafe fn get_string<T>(instance: NonNull<T>, selector: Selector) -> String {
+        let string: *mut () = msg_send(Instance::from_ptr(instance), selector, ());
+        match NonNull::new(string) {
+            Some(string) => string_from_nsstring(string),
+            None => String::new(),
+        }
+    }
+
+    /// `OBJC_ASSOCIATION_RETAIN`: the associated object is retained, atomically.
+    const ASSOCIATION_RETAIN: usize = 0o1401;
+
+    /// Returns the string cached on `instance` under `key`. The first time, `load` is
+    /// called to get the string, and it's cached on the instance (as an associated
+    /// object) until the instance is deallocated. This is how `#[cached]` getters are
+    /// implemented; every later call is one runtime call, with no messages sent and
+    /// nothing copied.
+    ///
+    /// The cache is never invalidated, so it's only correct for strings that don't
+    /// change for the life of the instance, like an identifier.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418960-objc_getassociatedobject?language=objc
+    ///
+    /// # Safety
+    /// `instance` must point to a valid Objective-C object, which has to outlive the
+    /// returned string.
+    pub unsafe fn cached_string<'a, T>(
+        instance: NonNull<T>,
+        key: &'static u8,
+        load: impl FnOnce() -> String,
+    ) -> &'a str {
+        let instance = instance.as_ptr().cast::<()>();
+        let key: *const () = (key as *const u8).cast();
+        let cached = objc_getAssociatedObject(instance, key);
+        if let Some(cached) = NonNull::new(cached) {
+            return CachedString::get(cached);
+        }
+
+        // The string's loaded outside the lock, since getters can call other getters.
+        let string = load();
+        // Replacing a cached string would free it while it could still be borrowed, so
+        // only the first thread to load it caches it.
+        static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let _guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let cached = match NonNull::new(objc_getAssociatedObject(instance, key)) {
+            Some(cached) => cached,
+            None => {
+                let cached = CachedString::create(string);
+                objc_setAssociatedObject(instance, key, cached.as_ptr(), ASSOCIATION_RETAIN);
+                // The instance holds the only reference now.
+                release(cached);
+                cached
+            }
+        };
+
+        CachedString::get(cached)
+    }
+
+    /// An `ObjectiveRustCachedString`, the object [`cached_string`] associates with an
+    /// instance. It owns a boxed [`String`], which it frees when it's deallocated.
+    struct CachedString;
+    impl CachedString {
+        fn create(string: String) -> NonNull<()> {
+            let (class, _) = Self::class();
+            let string: *mut String = Box::into_raw(Box::new(string));
+            unsafe {
+                let object: *mut () = msg_send(Instance(class.0), get_selector("new").unwrap(), ());
+                let object = NonNull::new(object).unwrap();
+                object_set_ivar(Instance(object), "string", string);
+
+                object
+            }
+        }
+
+        /// Reads the string straight from the ivar, since its offset is known.
+        unsafe fn get<'a>(object: NonNull<()>) -> &'a str {
+            let (_, offset) = Self::class();
+            let string = *object
+                .as_ptr()
+                .cast::<u8>()
+                .offset(offset)
+                .cast::<*const String>();
+            &*string
+        }
+
+        fn class() -> (Class, isize) {
+            static CLASS: std::sync::OnceLock<(Class, isize)> = std::sync::OnceLock::new();
+            *CLASS.get_or_init(|| {
+                let superclass = get_class("NSObject").unwrap();
+                let mut builder = ClassBuilder::new("ObjectiveRustCachedString", superclass)
+                    .expect("`ObjectiveRustCachedString` is already defined");
+                builder.add_ivar::<*mut String>("string");
+                unsafe {
+                    builder.add_method(
+                        get_selector("dealloc").unwrap(),
+                        Self::dealloc as extern "C-unwind" fn(_, _) as *const (),
+                        "v@:",
+                    );
+                }
+                let class = builder.register();
+
+                let name = CString::new("string").unwrap();
+                let ivar = unsafe { class_getInstanceVariable(class, name.as_ptr()) };
+                let offset = unsafe { ivar_getOffset(ivar) };
+
+                (class, offset)
+            })
+        }
+
+        extern "C-unwind" fn dealloc(this: Instance, cmd: Selector) {
+            let string: *mut String =
+                unsafe { object_get_ivar(this, "string") }.unwrap_or(core::ptr::null_mut());
+            if !string.is_null() {
+                drop(unsafe { Box::from_raw(string) });
+            }
+
+            let receiver = ObjcSuper {
+                receiver: this.as_ptr(),
+                super_class: get_class("NSObject").unwrap(),
+            };
+            unsafe {
+                let func: extern "C" fn(*const ObjcSuper, Selector) =
+                    core::mem::transmute(get_msg_send_super_for::<()>());
+                func(&receiver, cmd)
+            }
+        }
+    }
+
+    /// Writes an object's `description` to a formatter, or `<nil description>` if it
+    /// was nil. This is how bound classes implement [`Debug`](core::fmt::Debug).
+    ///
+    /// The description is autoreleased, so it's only freed if there's an
+    /// [`AutoreleasePool`] around the call.
+    ///
+    /// # Safety
+    /// `instance` must point to a valid Objective-C object.
+    pub unsafe fn write_description<T>(
+        instance: NonNull<T>,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        static DESCRIPTION: std::sync::OnceLock<Selector> = std::sync::OnceLock::new();
+        let selector = *DESCRIPTION.get_or_init(|| get_selector("description").unwrap());
+
+        let description: *mut () = msg_send(Instance::from_ptr(instance), selector, ());
+        if description.is_null() {
+            return f.write_str("<nil description>");
+        }
+
+        f.write_str(NSStr::from_ptr(description).as_str())
+    }
+
+    /// Compares two objects with `-[a compare:b]`. This is how classes marked
+    /// `#[comparable]` implement [`Ord`].
+    ///
+    /// # Safety
+    /// Both objects must be valid, and `a` must have a `compare:` method that accepts
+    /// `b` and returns an `NSComparisonResult`.
+    pub unsafe fn compare<T>(a: NonNull<T>, b: NonNull<T>) -> core::cmp::Ordering {
+        static COMPARE: std::sync::OnceLock<Selector> = std::sync::OnceLock::new();
+        let selector = *COMPARE.get_or_init(|| get_selector("compare:").unwrap());
+
+        let result: crate::foundation::NSComparisonResult =
+            msg_send(Instance::from_ptr(a), selector, (b,));
+        result.into()
+    }
+
+    /// A borrowed `NSString`, returned by methods declared to return `NSStr`. It isn't
+    /// retained or released, and can't outlive the object it was returned from, so it's
+    /// a cheap way to look at a string property (like a window's `title`) without
+    /// copying it into a [`String`].
+    #[derive(Clone, Copy)]
+    pub struct NSStr<'a> {
+        ptr: Option<NonNull<()>>,
+        _lifetime: core::marker::PhantomData<&'a ()>,
+    }
+    impl NSStr<'_> {
+        /// Creates a borrowed view of an `NSString`. Null pointers are treated as an
+        /// empty string.
+        ///
+        /// # Safety
+        /// `ptr` must be null or point to a valid `NSString` that lives (and isn't
+        /// mutated) for as long as the returned `NSStr`.
+        pub unsafe fn from_ptr(ptr: *mut ()) -> Self {
+            Self {
+                ptr: NonNull::new(ptr),
+                _lifetime: core::marker::PhantomData,
+            }
+        }
+
+        /// Returns `true` if the method returned nil.
+        pub fn is_nil(&self) -> bool {
+            self.ptr.is_none()
+        }
+
+        /// Returns the pointer to the `NSString`, or null if it was nil.
+        pub fn as_ptr(&self) -> *mut () {
+            self.ptr.map_or(core::ptr::null_mut(), NonNull::as_ptr)
+        }
+
+        /// Returns the string's contents, without copying them.
+        pub fn as_str(&self) -> &str {
+            let Some(ptr) = self.ptr else {
+                return "";
+            };
+
+            // `UTF8String`'s buffer lives as long as the string does.
+            let bytes = unsafe { utf8_bytes(ptr) };
+            std::str::from_utf8(bytes).unwrap_or_default()
+        }
+    }
+    impl core::fmt::Display for NSStr<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str(self.as_str())
+        }
+    }
+    impl core::fmt::Debug for NSStr<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            core::fmt::Debug::fmt(self.as_str(), f)
+        }
+    }
+
+    /// An owned `NSString`, created from Rust (usually with [`ns_format!`](crate::ns_format)).
+    /// It's released when dropped.
+    ///
+    /// To pass it to a bound method, use [`NSString::as_ptr`] for `*mut ()` arguments,
+    /// or [`NSString::into_raw`] and your own `NSString` binding's `from_raw` to
+    /// convert it.
+    pub struct NSString(NonNull<()>);
+    impl NSString {
+        /// Copies `string` into a new `NSString`.
+        ///
+        /// The string can contain NULs; its length is passed to Objective-C, so it
+        /// doesn't need to be NUL-terminated.
+        pub fn new(string: &str) -> Self {
+            let class = get_class("NSString").unwrap();
+            unsafe {
+                let ptr: *mut () = msg_send(Instance(class.0), get_selector("alloc").unwrap(), ());
+                let ptr: *mut () = msg_send(
+                    Instance::from_ptr(NonNull::new(ptr).unwrap()),
+                    get_selector("initWithBytes:length:encoding:").unwrap(),
+                    (string.as_ptr(), string.len(), UTF8),
+                );
+
+                Self(NonNull::new(ptr).unwrap())
+            }
+        }
+
+        /// Returns the pointer to the `NSString`. The string is still owned by `self`.
+        pub fn as_ptr(&self) -> *mut () {
+            self.0.as_ptr()
+        }
+
+        /// Gives up ownership of the string, returning its pointer. The caller is
+        /// responsible for releasing it.
+        pub fn into_raw<T>(self) -> NonNull<T> {
+            let ptr = self.0.cast();
+            core::mem::forget(self);
+            ptr
+        }
+
+        /// Borrows the string as an [`NSStr`].
+        pub fn as_nsstr(&self) -> NSStr<'_> {
+            unsafe { NSStr::from_ptr(self.as_ptr()) }
+        }
+    }
+    impl Drop for NSString {
+        fn drop(&mut self) {
+            unsafe { release(self.0) }
+        }
+    }
+    impl core::fmt::Display for NSString {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            core::fmt::Display::fmt(&self.as_nsstr(), f)
+        }
+    }
+    impl core::fmt::Debug for NSString {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            core::fmt::Debug::fmt(&self.as_nsstr(), f)
+        }
+    }
+
+    /// An Objective-C exception, caught by [`catch_exception`] or a method marked
+    /// `#[throws]`. Exceptions are almost always `NSException`s, but any object can be
+    /// thrown. It's released when dropped.
+    pub struct Exception(NonNull<()>);
+    impl Exception {
+        /// Returns the pointer to the thrown object. It's still owned by `self`.
+        pub fn as_ptr(&self) -> *mut () {
+            self.0.as_ptr()
+        }
+
+        /// The exception's `name`, like `NSRangeException`. Returns an empty string if
+        /// the thrown object isn't an `NSException`.
+        pub fn name(&self) -> String {
+            self.get_string("name")
+        }
+
+        /// The exception's `reason`, a human-readable explanation of what went wrong.
+        /// Returns an empty string if there isn't one, or if the thrown object isn't an
+        /// `NSException`.
+        pub fn reason(&self) -> String {
+            self.get_string("reason")
+        }
+
+        fn get_string(&self, selector: &str) -> String {
+            let selector = get_selector(selector).unwrap();
+            // Sending a message the object doesn't understand would throw again, and
+            // there'd be nothing to catch it.
+            let responds: crate::ObjcBool = unsafe {
+                msg_send(
+                    Instance(self.0),
+                    get_selector("respondsToSelector:").unwrap(),
+                    (selector,),
+                )
+            };
+            if !bool::from(responds) {
+                return String::new();
+            }
+
+            unsafe { get_string(self.0, selector) }
+        }
+    }
+    impl Drop for Exception {
+        fn drop(&mut self) {
+            unsafe { release(self.0) }
+        }
+    }
+    impl core::fmt::Display for Exception {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(f, "{}: {}", self.name(), self.reason())
+        }
+    }
+    impl core::fmt::Debug for Exception {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("Exception")
+                .field("name", &self.name())
+                .field("reason", &self.reason())
+                .finish()
+        }
+    }
+    impl std::error::Error for Exception {}
+
+    /// An `NSError`, returned by methods marked `#[error_param]` when they fail. It's
+    /// released when dropped.
+    ///
+    /// Some methods fail without setting their error parameter. The `NSError` is still
+    /// returned, but it's empty: it has an empty domain and description, and a code of 0.
+    pub struct NSError(Option<NonNull<()>>);
+    impl NSError {
+        /// Wraps the error a method set its `NSError **` parameter to, retaining it
+        /// (errors returned that way are autoreleased). Null means the method didn't set
+        /// one.
+        ///
+        /// # Safety
+        /// `ptr` must be null or point to a valid `NSError`.
+        pub unsafe fn from_out_param(ptr: *mut ()) -> Self {
+            Self(NonNull::new(ptr).map(|ptr| retain(ptr)))
+        }
+
+        /// Returns the pointer to the `NSError`, or null if the method didn't set one.
+        /// It's still owned by `self`.
+        pub fn as_ptr(&self) -> *mut () {
+            self.0.map_or(core::ptr::null_mut(), NonNull::as_ptr)
+        }
+
+        /// The error's `domain`, like `NSCocoaErrorDomain`.
+        pub fn domain(&self) -> String {
+            match self.0 {
+                Some(ptr) => unsafe { get_string(ptr, get_selector("domain").unwrap()) },
+                None => String::new(),
+            }
+        }
+
+        /// The error's `code`. Codes are only unique within the error's domain.
+        pub fn code(&self) -> isize {
+            match self.0 {
+                Some(ptr) => unsafe { msg_send(Instance(ptr), get_selector("code").unwrap(), ()) },
+                None => 0,
+            }
+        }
+
+        /// The error's `localizedDescription`, a message that can be shown to users.
+        pub fn localized_description(&self) -> String {
+            match self.0 {
+                Some(ptr) => unsafe {
+                    get_string(ptr, get_selector("localizedDescription").unwrap())
+                },
+                None => String::new(),
+            }
+        }
+    }
+    impl Drop for NSError {
+        fn drop(&mut self) {
+            if let Some(ptr) = self.0 {
+                unsafe { release(ptr) }
+            }
+        }
+    }
+    impl core::fmt::Display for NSError {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self.0 {
+                Some(_) => f.write_str(&self.localized_description()),
+                None => f.write_str("the method failed without returning an error"),
+            }
+        }
+    }
+    impl core::fmt::Debug for NSError {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("NSError")
+                .field("domain", &self.domain())
+                .field("code", &self.code())
+                .finish()
+        }
+    }
+    impl std::error::Error for NSError {}
+
+    /// Why a bound class' `try_load` failed.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum LoadError {
+        /// The class isn't loaded, usually because the framework that defines it isn't
+        /// linked. For classes defined from Rust, this is their superclass.
+        Class(&'static str),
+        /// The class doesn't respond to a bound method's selector. It may be misspelled,
+        /// or only exist on newer OS versions.
+        Selector {
+            class: &'static str,
+            selector: &'static str,
+        },
+        /// A bound method's arguments or return type don't match the method's real
+        /// signature. This is only checked in debug builds.
+        Encoding {
+            class: &'static str,
+            selector: &'static str,
+        },
+    }
+    impl core::fmt::Display for LoadError {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                Self::Class(class) => write!(f, "the class `{class}` isn't loaded"),
+                Self::Selector { class, selector } => {
+                    write!(f, "`{class}` doesn't respond to the selector `{selector}`")
+                }
+                Self::Encoding { class, selector } => {
+                    write!(
+                        f,
+                        "`{class}`'s method `{selector}` is bound with the wrong types"
+                    )
+                }
+            }
+        }
+    }
+    impl std::error::Error for LoadError {}
+
+    /// Calls `func`, catching any Objective-C exception it throws. This is what methods
+    /// marked `#[throws]` use, and it's useful for [`msg_send`] calls that can throw.
+    ///
+    /// Without this, an exception unwinding into Rust aborts the process. It's also
+    /// only caught if every function it unwinds through is declared with the
+    /// `"C-unwind"` ABI: [`msg_send`] uses `"C"`, so transmute
+    /// [`get_msg_send`] to an `extern "C-unwind" fn` for calls that can throw. Rust
+    /// panics in `func` aren't caught; they unwind past this as usual.
+    ///
+    /// https://developer.apple.com/documentation/foundation/nsexception?language=objc
+    pub fn catch_exception<F: FnOnce() -> R, R>(func: F) -> Result<R, Exception> {
+        struct Context<F, R> {
+            func: Option<F>,
+            result: Option<R>,
+        }
+        extern "C-unwind" fn call<F: FnOnce() -> R, R>(context: *mut ()) {
+            let context = unsafe { &mut *context.cast::<Context<F, R>>() };
+            let func = context.func.take().unwrap();
+            context.result = Some(func());
+        }
+
+        let mut context = Context {
+            func: Some(func),
+            result: None,
+        };
+        let exception = unsafe {
+            objective_rust_try(call::<F, R>, (&mut context as *mut Context<F, R>).cast())
+        };
+
+        match NonNull::new(exception) {
+            Some(exception) => Err(Exception(exception)),
+            None => Ok(context.result.unwrap()),
+        }
+    }
+
+    /// What a method implemented in Rust does when it panics, set on its class with
+    /// `#[on_panic(...)]`. A panic can't unwind into the Objective-C code that called
+    /// the method, so it has to be stopped at the method's boundary.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum PanicStrategy {
+        /// Print which method panicked, and abort the process. This is the default.
+        Abort,
+        /// Raise an `NSException` named `RustPanic`, whose reason is the panic's
+        /// message. Objective-C code can catch it with `@try`, and Rust code with
+        /// [`catch_exception`]; if nothing catches it, the process is terminated as
+        /// with any uncaught exception.
+        Throw,
+    }
+
+    /// Calls `func`, the Rust implementation of `class`' `selector` method, handling a
+    /// panic with `strategy`. Methods implemented in Rust are registered with a
+    /// function that wraps them in this.
+    pub fn call_rust_method<F: FnOnce() -> R, R>(
+        strategy: PanicStrategy,
+        class: &str,
+        selector: &str,
+        func: F,
+    ) -> R {
+        // The panic is never observed after it's caught, so broken invariants can't be.
+        let payload = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(func)) {
+            Ok(result) => return result,
+            Err(payload) => payload,
+        };
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("Box<dyn Any>");
+        let reason = format!("`{class}`'s `{selector}` panicked: {message}");
+        drop(payload);
+
+        match strategy {
+            PanicStrategy::Abort => {
+                eprintln!("objective-rust: {reason}; aborting");
+                std::process::abort()
+            }
+            PanicStrategy::Throw => {
+                let name = NSString::new("RustPanic");
+                let reason = NSString::new(&reason);
+                unsafe {
+                    let exception: *mut () = msg_send(
+                        Instance(get_class("NSException").unwrap().0),
+                        get_selector("exceptionWithName:reason:userInfo:").unwrap(),
+                        (name.as_ptr(), reason.as_ptr(), core::ptr::null_mut::<()>()),
+                    );
+                    // The exception retains its name and reason, and nothing after the
+                    // throw runs.
+                    drop(name);
+                    drop(reason);
+                    objc_exception_throw(exception)
+                }
+            }
+        }
+    }
+
+    /// The callback signature [`Callback`] supports: a C function that takes the
+    /// `void *` context it was registered with. Use this type for the function
+    /// argument when binding methods that take a callback and a context.
+    pub type CallbackFn = extern "C" fn(context: *mut ());
+
+    /// A Rust closure, bundled up for C-style APIs that take a callback function and a
+    /// `void *` context to call it with, like `CFRunLoopObserverCreate` or
+    /// `dispatch_async_f`. [`Callback::as_raw`] returns a trampoline function and a
+    /// pointer to the boxed closure, which are passed as that pair.
+    ///
+    /// The closure lives as long as the `Callback` does, and is freed when it's
+    /// dropped, so it has to outlive every call the API makes. For APIs that hold on to
+    /// the callback indefinitely, use [`Callback::into_raw`] to leak it, and
+    /// [`Callback::from_raw`] to free it once the API is done with it (often in a
+    /// separate release callback).
+    ///
+    /// Panics in the closure can't unwind into C, so they abort the process.
+    pub struct Callback<F: FnMut()>(Box<F>);
+    impl<F: FnMut()> Callback<F> {
+        /// Boxes `func` so it can be passed as a context pointer.
+        pub fn new(func: F) -> Self {
+            Self(Box::new(func))
+        }
+
+        /// Returns the trampoline function and the context to pass it. They're valid
+        /// for as long as `self` is alive.
+        pub fn as_raw(&mut self) -> (CallbackFn, *mut ()) {
+            let context: *mut F = &mut *self.0;
+            (Self::trampoline, context.cast())
+        }
+
+        /// Gives up ownership of the closure, returning the trampoline function and the
+        /// context to pass it. The closure is leaked unless it's reclaimed with
+        /// [`Callback::from_raw`].
+        pub fn into_raw(self) -> (CallbackFn, *mut ()) {
+            (Self::trampoline, Box::into_raw(self.0).cast())
+        }
+
+        /// Takes back ownership of a closure given up with [`Callback::into_raw`], so it's
+        /// freed when the returned `Callback` is dropped.
+        ///
+        /// # Safety
+        /// - `context` must be a context returned by `into_raw` on a `Callback<F>`,
+        ///   with the same `F`.
+        /// - It can't be reclaimed more than once, and the API can't call the
+        ///   callback again afterwards (unless it gets the new `as_raw` context).
+        pub unsafe fn from_raw(context: *mut ()) -> Self {
+            Self(Box::from_raw(context.cast()))
+        }
+
+        extern "C" fn trampoline(context: *mut ()) {
+            let func = unsafe { &mut *context.cast::<F>() };
+            func()
+        }
+    }
+
+    /// A Rust closure wrapped in an Objective-C object, for APIs that call back by
+    /// sending a message to an object instead of calling a function, like AppKit's
+    /// target-action. Pass [`ClosureTarget::as_ptr`] as the object, and
+    /// [`ClosureTarget::selector`] as the selector to send it, or use
+    /// [`ClosureTarget::attach_to`] for a control.
+    ///
+    /// The object is an instance of `ObjectiveRustClosureTarget`, an `NSObject`
+    /// subclass that's defined the first time a `ClosureTarget` is made. Its only
+    /// method, `invoke:`, calls the closure, and ignores its argument (usually the
+    /// sender, or the notification).
+    ///
+    /// The closure is freed when the `ClosureTarget` is dropped. If something still
+    /// holds on to the object after that, `invoke:` does nothing, but APIs that don't
+    /// retain their target (like `NSControl`) will crash if they message it once it's
+    /// deallocated, so keep the `ClosureTarget` alive as long as they're using it.
+    ///
+    /// The closure is called on whichever thread sends `invoke:`, and it isn't `Send`,
+    /// so only hand the object to APIs that message it on the thread that made the
+    /// `ClosureTarget` - like AppKit controls, which send their action on the main
+    /// thread. The closure is a `Fn`, since `invoke:` can be called again while it's
+    /// running; use a `Cell` or `RefCell` for state it changes. Panics in the closure
+    /// abort the process, like methods implemented in Rust do by default.
+    pub struct ClosureTarget {
+        object: NonNull<()>,
+        func: *mut Box<dyn Fn()>,
+    }
+    impl ClosureTarget {
+        /// Boxes `func`, and creates an object that calls it.
+        pub fn new(func: impl Fn() + 'static) -> Self {
+            let func: *mut Box<dyn Fn()> = Box::into_raw(Box::new(Box::new(func)));
+            let class = Self::class();
+            unsafe {
+                let object: *mut () = msg_send(Instance(class.0), get_selector("new").unwrap(), ());
+                let object = NonNull::new(object).unwrap();
+                object_set_ivar(Instance(object), "closure", func);
+
+                Self { object, func }
+            }
+        }
+
+        /// Returns the pointer to the object. It's still owned by `self`.
+        pub fn as_ptr(&self) -> *mut () {
+            self.object.as_ptr()
+        }
+
+        /// The selector of the object's method that calls the closure, `invoke:`.
+        pub fn selector() -> Selector {
+            get_selector("invoke:").unwrap()
+        }
+
+        /// Makes this the target of an AppKit control (an `NSControl`, like an
+        /// `NSButton`, or an `NSMenuItem`), with `setTarget:`, and sets the control's
+        /// action to [`ClosureTarget::selector`] with `setAction:`, so the closure is
+        /// called when the control is clicked.
+        ///
+        /// Controls don't retain their target, so `self` has to outlive the control, or
+        /// the control's target has to be changed before `self` is dropped.
+        ///
+        /// https://developer.apple.com/documentation/appkit/nscontrol/1428885-target?language=objc
+        ///
+        /// # Safety
+        /// `control` must be a valid object that responds to `setTarget:` and
+        /// `setAction:`, and only sends its action on this thread.
+        pub unsafe fn attach_to(&self, control: *mut ()) {
+            let control = Instance(NonNull::new(control).unwrap());
+            msg_send::<(), _>(
+                control,
+                get_selector("setTarget:").unwrap(),
+                (self.as_ptr(),),
+            );
+            msg_send::<(), _>(
+                control,
+                get_selector("setAction:").unwrap(),
+                (Self::selector(),),
+            );
+        }
+
+        fn class() -> Class {
+            static CLASS: std::sync::OnceLock<Class> = std::sync::OnceLock::new();
+            *CLASS.get_or_init(|| {
+                let superclass = get_class("NSObject").unwrap();
+                let mut builder = ClassBuilder::new("ObjectiveRustClosureTarget", superclass)
+                    .expect("`ObjectiveRustClosureTarget` is already defined");
+                builder.add_ivar::<*mut Box<dyn Fn()>>("closure");
+                unsafe {
+                    builder.add_method(
+                        Self::selector(),
+                        Self::invoke as extern "C-unwind" fn(_, _, _) as *const (),
+                        "v@:@",
+                    );
+                }
+
+                builder.register()
+            })
+        }
+
+        extern "C-unwind" fn invoke(this: Instance, _cmd: Selector, _sender: *mut ()) {
+            let func: *mut Box<dyn Fn()> =
+                unsafe { object_get_ivar(this, "closure") }.unwrap_or(core::ptr::null_mut());
+            if func.is_null() {
+                return;
+            }
+
+            let func = unsafe { &*func };
+            call_rust_method(
+                PanicStrategy::Abort,
+                "ObjectiveRustClosureTarget",
+                "invoke:",
+                func,
+            )
+        }
+    }
+    impl Drop for ClosureTarget {
+        fn drop(&mut self) {
+            unsafe {
+                object_set_ivar(
+                    Instance(self.object),
+                    "closure",
+                    core::ptr::null_mut::<()>(),
+                );
+                release(self.object);
+                drop(Box::from_raw(self.func));
+            }
+        }
+    }
+
+    /// Observes a notification posted to the default `NSNotificationCenter`, calling a
+    /// closure each time it's posted, until the `NotificationObserver` is dropped.
+    ///
+    /// The closure is registered as a [`Block`] with
+    /// `addObserverForName:object:queue:usingBlock:`, on the main queue, so it's always
+    /// called on the main thread - even for notifications posted from other threads,
+    /// which wait for it - and doesn't need to be `Send`. The notification center keeps
+    /// its own copy of the block, so the closure lives until the center is done with
+    /// it, even if that's after the `NotificationObserver` is dropped.
+    ///
+    /// ```ignore
+    /// let observer = NotificationObserver::new(
+    ///     "NSWindowDidResizeNotification",
+    ///     window.as_ptr(),
+    ///     || println!("resized"),
+    /// );
+    /// ```
+    ///
+    /// https://developer.apple.com/documentation/foundation/nsnotificationcenter/1411723-addobserverforname?language=objc
+    pub struct NotificationObserver {
+        observer: NonNull<()>,
+    }
+    impl NotificationObserver {
+        /// Calls `func` whenever a notification named `name` is posted. If `object` isn't
+        /// null, only notifications posted by that object are observed.
+        ///
+        /// # Panics
+        /// If it isn't called on the main thread.
+        pub fn new(name: &str, object: *mut (), func: impl Fn() + 'static) -> Self {
+            assert!(
+                Self::is_main_thread(),
+                "`NotificationObserver`s have to be made on the main thread"
+            );
+
+            let block = Block::<dyn Fn(*mut ())>::new(move |_notification| func());
+            let name = NSString::new(name);
+            let observer = autoreleasepool(|| unsafe {
+                let queue: *mut () = msg_send(
+                    Instance(get_class("NSOperationQueue").unwrap().0),
+                    get_selector("mainQueue").unwrap(),
+                    (),
+                );
+                let observer: *mut () = msg_send(
+                    Self::center(),
+                    get_selector("addObserverForName:object:queue:usingBlock:").unwrap(),
+                    (name.as_ptr(), object, queue, block.as_ptr()),
+                );
+                retain(NonNull::new(observer).unwrap())
+            });
+
+            Self { observer }
+        }
+
+        fn is_main_thread() -> bool {
+            let is_main: crate::ObjcBool = unsafe {
+                msg_send(
+                    Instance(get_class("NSThread").unwrap().0),
+                    get_selector("isMainThread").unwrap(),
+                    (),
+                )
+            };
+
+            is_main.into()
+        }
+
+        fn center() -> Instance {
+            let class = get_class("NSNotificationCenter").unwrap();
+            let center: *mut () = unsafe {
+                msg_send(
+                    Instance(class.0),
+                    get_selector("defaultCenter").unwrap(),
+                    (),
+                )
+            };
+
+            Instance(NonNull::new(center).unwrap())
+        }
+    }
+    impl Drop for NotificationObserver {
+        fn drop(&mut self) {
+            unsafe {
+                msg_send::<(), _>(
+                    Self::center(),
+                    get_selector("removeObserver:").unwrap(),
+                    (self.observer.as_ptr(),),
+                );
+                release(self.observer);
+            }
+        }
+    }
+
+    /// Sends a message to an Objective-C object with `objc_msgSend`, for one-off calls
+    /// to methods that don't have bindings. `args` is a tuple of the method's arguments
+    /// (use `()` for no arguments), and `R` is its return type. Large return types go
+    /// through `objc_msgSend_stret` (see [`get_msg_send_for`]).
+    ///
+    /// ```ignore
+    /// let is_window: ObjcBool = unsafe {
+    ///     msg_send(instance, get_selector("isKindOfClass:").unwrap(), (class,))
+    /// };
+    /// ```
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1456712-objc_msgsend?language=objc
+    ///
+    /// # Safety
+    /// Nothing about the call can be checked, so:
+    /// - `receiver` must be a valid object (or class, for class methods).
+    /// - The method for `selector` must take exactly the arguments in `A`, in order,
+    ///   with the same types and layouts as the real Objective-C signature.
+    /// - `R` must match the method's real return type. Use `()` for `void`.
+    #[inline(always)]
+    pub unsafe fn msg_send<R, A: MsgArgs>(receiver: Instance, selector: Selector, args: A) -> R {
+        args.invoke(get_msg_send_for::<R>().0.as_ptr(), receiver, selector)
+    }
+
+    /// An argument list for [`msg_send`]. This is implemented for tuples of up to
+    /// 8 arguments.
+    pub trait MsgArgs {
+        /// Calls `imp` with the receiver, selector, and these arguments.
+        ///
+        /// # Safety
+        /// `imp` must be a method implementation (or `objc_msgSend`) whose signature
+        /// matches these arguments and `R`.
+        unsafe fn invoke<R>(self, imp: *const (), receiver: Instance, selector: Selector) -> R;
+    }
+    macro_rules! impl_msg_args {
+        ($($ty:ident $arg:ident),*) => {
+            impl<$($ty),*> MsgArgs for ($($ty,)*) {
+                #[inline(always)]
+                unsafe fn invoke<R>(self, imp: *const (), receiver: Instance, selector: Selector) -> R {
+                    let ($($arg,)*) = self;
+                    let func: unsafe extern "C" fn(Instance, Selector $(, $ty)*) -> R =
+                        core::mem::transmute(imp);
+
+                    func(receiver, selector $(, $arg)*)
+                }
+            }
+        };
+    }
+    impl_msg_args!();
+    impl_msg_args!(A a);
+    impl_msg_args!(A a, B b);
+    impl_msg_args!(A a, B b, C c);
+    impl_msg_args!(A a, B b, C c, D d);
+    impl_msg_args!(A a, B b, C c, D d, E e);
+    impl_msg_args!(A a, B b, C c, D d, E e, F f);
+    impl_msg_args!(A a, B b, C c, D d, E e, F f, G g);
+    impl_msg_args!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+    /// Returns an object's retain count, via its `retainCount` method. Only available in
+    /// debug builds.
+    ///
+    /// Retain counts are unreliable for real logic: the runtime, autorelease pools, and
+    /// framework internals all retain objects behind your back, and some objects (like
+    /// tagged pointers and singletons) report meaningless counts. They're useful for
+    /// leak tests, though, where you compare an object's count before and after an
+    /// operation that shouldn't change it.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418956-nsobject/1571952-retaincount?language=objc
+    ///
+    /// # Safety
+    /// `instance` must point to a valid Objective-C object.
+    #[cfg(debug_assertions)]
+    pub unsafe fn retain_count<T>(instance: NonNull<T>) -> usize {
+        msg_send(
+            Instance::from_ptr(instance),
+            get_selector("retainCount").unwrap(),
+            (),
+        )
+    }
+
+    /// Returns every method implemented directly by `class`. Methods inherited from
+    /// superclasses aren't included.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418490-class_copymethodlist?language=objc
+    pub fn get_methods(class: Class) -> Vec<Method> {
+        let mut count = 0;
+        let list = unsafe { class_copyMethodList(class, &mut count) };
+        if list.is_null() {
+            return Vec::new();
+        }
+
+        let methods = unsafe { std::slice::from_raw_parts(list, count as usize) }.to_vec();
+        unsafe { free(list.cast()) };
+
+        methods
+    }
+
+    /// Returns the selector for a [`Method`].
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418758-method_getname?language=objc
+    pub fn get_method_selector(method: Method) -> Selector {
+        unsafe { method_getName(method) }
+    }
+
+    /// Returns the name of a [`Selector`], like `initWithContentRect:styleMask:backing:defer:`.
+    ///
+    /// https://developer.apple.com/documentation/objectivec/1418571-sel_getname?language=objc
+    pub fn get_selector_name(selector: Selector) -> &'static str {
+        let name = unsafe { CStr::from_ptr(sel_getName(selector)) };
+        name.to_str().unwrap_or_default()
+    }
+
+    /// A class whose methods were discovered at runtime with [`get_methods`], so they can
+    /// be called without declaring bindings for them. This is meant for exploring
+    /// unfamiliar classes; it isn't type safe at all.
+    ///
+    /// [`call`](Self::call) only supports methods whose arguments and return value are
+    /// all pointer-sized (objects, pointers, and integers up to 64 bits), because it can't
+    /// know the method's real signature. Methods inherited from superclasses aren't
+    /// found, either.
+    pub struct ReflectedClass {
+        class: Class,
+        methods: HashMap<&'static str, (Selector, Implementation)>,
+    }
+    impl ReflectedClass {
+        /// Loads every method of `class`. Pass a metaclass to load class methods instead
+        /// of instance methods.
+        pub fn new(class: Class) -> Self {
+            let methods = get_methods(class)
+                .into_iter()
+                .filter_map(|method| {
+                    let sel = get_method_selector(method);
+                    let imp = get_method_impl(class, sel)?;
+
+                    Some((get_selector_name(sel), (sel, imp)))
+                })
+                .collect();
+
+            Self { class, methods }
+        }
+
+        /// The class these methods were loaded from.
+        pub fn class(&self) -> Class {
+            self.class
+        }
+
+        /// The names of every method that was found.
+        pub fn selectors(&self) -> impl Iterator<Item = &'static str> + '_ {
+            self.methods.keys().copied()
+        }
+
+        /// Calls the method named `selector` on `receiver`. Returns `None` if the method
+        /// wasn't found, or if more than 6 arguments were given.
+        ///
+        /// # Safety
+        /// - `receiver` must be an instance of the class (or the class itself, if this
+        ///   was created from a metaclass).
+        /// - The method must take exactly `args.len()` arguments, and they, and its
+        ///   return value, must all be pointer-sized. If the method returns nothing, the
+        ///   returned pointer is garbage and must not be used.
+        pub unsafe fn call(
+            &self,
+            receiver: *mut (),
+            selector: &str,
+            args: &[*mut ()],
+        ) -> Option<*mut ()> {
+            type P = *mut ();
+            let (sel, imp) = *self.methods.get(selector)?;
+            let imp = imp.0.as_ptr();
+
+            let result = match *args {
+                [] => {
+                    let func: extern "C" fn(P, Selector) -> P = core::mem::transmute(imp);
+                    func(receiver, sel)
+                }
+                [a] => {
+                    let func: extern "C" fn(P, Selector, P) -> P = core::mem::transmute(imp);
+                    func(receiver, sel, a)
+                }
+                [a, b] => {
+                    let func: extern "C" fn(P, Selector, P, P) -> P = core::mem::transmute(imp);
+                    func(receiver, sel, a, b)
+                }
+                [a, b, c] => {
+                    let func: extern "C" fn(P, Selector, P, P, P) -> P = core::mem::transmute(imp);
+                    func(receiver, sel, a, b, c)
+                }
+                [a, b, c, d] => {
+                    let func: extern "C" fn(P, Selector, P, P, P, P) -> P =
+                        core::mem::transmute(imp);
+                    func(receiver, sel, a, b, c, d)
+                }
+                [a, b, c, d, e] => {
+                    let func: extern "C" fn(P, Selector, P, P, P, P, P) -> P =
+                        core::mem::transmute(imp);
+                    func(receiver, sel, a, b, c, d, e)
+                }
+                [a, b, c, d, e, f] => {
+                    let func: extern "C" fn(P, Selector, P, P, P, P, P, P) -> P =
+                        core::mem::transmute(imp);
+                    func(receiver, sel, a, b, c, d, e, f)
+                }
+                _ => return None,
+            };
+
+            Some(result)
+        }
+    }
+
+    /// Declares functions from the Objective-C runtime. On other platforms (which
+    /// only build with the `stubs` feature), each one is replaced with a stub that
+    /// panics, so nothing needs to link to the runtime.
+    macro_rules! objc_runtime {
+        ($(fn $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)?;)*) => {
+            #[cfg(target_os = "macos")]
+            #[link(name = "objc")]
+            extern "C" {
+                $(fn $name($($arg: $ty),*) $(-> $ret)?;)*
+            }
+
+            $(
+                #[cfg(not(target_os = "macos"))]
+                #[allow(non_snake_case, unused_variables)]
+                unsafe extern "C-unwind" fn $name($($arg: $ty),*) $(-> $ret)? {
+                    panic!("objective-rust only supports macOS")
+                }
+            )*
+        };
+    }
+    objc_runtime! {
+        fn class_getMethodImplementation(cls: Class, name: Selector) -> *mut ();
+        fn objc_retain(obj: *mut ()) -> *mut ();
+        fn objc_release(obj: *mut ());
+        fn objc_autoreleasePoolPush() -> *mut ();
+        fn objc_autoreleasePoolPop(context: *mut ());
+        fn objc_msgSend();
+        fn objc_msgSendSuper();
+        fn class_copyMethodList(cls: Class, out_count: *mut u32) -> *mut Method;
+        fn method_getName(method: Method) -> Selector;
+        fn sel_getName(sel: Selector) -> *const i8;
+        fn objc_getClass(name: *const i8) -> *mut ();
+        fn objc_getMetaClass(name: *const i8) -> *mut ();
+        fn sel_getUid(name: *const i8) -> *mut ();
+        fn objc_allocateClassPair(superclass: Class, name: *const i8, extra_bytes: usize) -> *mut ();
+        fn objc_registerClassPair(cls: Class);
+        fn class_addMethod(cls: Class, name: Selector, imp: *const (), types: *const i8) -> crate::ObjcBool;
+        fn object_getClass(obj: *mut ()) -> *mut ();
+        fn class_respondsToSelector(cls: Class, sel: Selector) -> crate::ObjcBool;
+        fn class_getName(cls: Class) -> *const i8;
+        fn objc_getProtocol(name: *const i8) -> *mut ();
+        fn class_conformsToProtocol(cls: Class, protocol: Protocol) -> crate::ObjcBool;
+        fn class_addProtocol(cls: Class, protocol: Protocol) -> crate::ObjcBool;
+        fn class_getSuperclass(cls: Class) -> *mut ();
+        fn objc_getClassList(buffer: *mut Class, buffer_count: i32) -> i32;
+        fn class_getInstanceMethod(cls: Class, name: Selector) -> *mut ();
+        fn class_getClassMethod(cls: Class, name: Selector) -> *mut ();
+        fn method_exchangeImplementations(m1: Method, m2: Method);
+        fn method_getTypeEncoding(method: Method) -> *const i8;
+        fn class_addIvar(cls: Class, name: *const i8, size: usize, alignment: u8, types: *const i8) -> crate::ObjcBool;
+        fn object_getInstanceVariable(obj: *mut (), name: *const i8, out: *mut *mut ()) -> *mut ();
+        fn objc_storeWeak(location: *mut *mut (), obj: *mut ()) -> *mut ();
+        fn objc_loadWeakRetained(location: *mut *mut ()) -> *mut ();
+        fn objc_copyWeak(to: *mut *mut (), from: *mut *mut ());
+        fn objc_destroyWeak(location: *mut *mut ());
+        fn object_setInstanceVariable(obj: *mut (), name: *const i8, value: *mut ()) -> *mut ();
+        fn class_getInstanceVariable(cls: Class, name: *const i8) -> *mut ();
+        fn ivar_getOffset(ivar: *mut ()) -> isize;
+        fn objc_getAssociatedObject(obj: *mut (), key: *const ()) -> *mut ();
+        fn objc_setAssociatedObject(obj: *mut (), key: *const (), value: *mut (), policy: usize);
+    }
+    // arm64 doesn't have `objc_msgSend_stret`, since `objc_msgSend` handles every
+    // return type there.
+    #[cfg(target_arch = "x86_64")]
+    objc_runtime! {
+        fn objc_msgSend_stret();
+        fn objc_msgSendSuper_stret();
+    }
+    extern "C" {
+        fn free(ptr: *mut std::ffi::c_void);
+    }
+    // From libSystem's blocks runtime, which every macOS process links.
+    #[cfg(target_os = "macos")]
+    extern "C" {
+        fn _Block_copy(block: *const ()) -> *const ();
+        fn _Block_release(block: *const ());
+    }
+    #[cfg(not(target_os = "macos"))]
+    #[allow(non_snake_case)]
+    unsafe fn _Block_copy(_block: *const ()) -> *const () {
+        panic!("objective-rust only supports macOS")
+    }
+    #[cfg(not(target_os = "macos"))]
+    #[allow(non_snake_case)]
+    unsafe fn _Block_release(_block: *const ()) {
+        panic!("objective-rust only supports macOS")
+    }
+    // `NSString` (and the rest of Foundation's classes) are only loaded if Foundation
+    // is linked.
+    #[cfg(target_os = "macos")]
+    #[link(name = "Foundation", kind = "framework")]
+    extern "C" {}
+
+    // Defined in `src/exception.m`, which `build.rs` compiles on macOS. It calls `func`
+    // inside an `@try` block, and returns the (retained) exception it threw, or null.
+    #[cfg(target_os = "macos")]
+    extern "C-unwind" {
+        fn objective_rust_try(func: extern "C-unwind" fn(*mut ()), context: *mut ()) -> *mut ();
+    }
+    #[cfg(not(target_os = "macos"))]
+    unsafe extern "C-unwind" fn objective_rust_try(
+        _func: extern "C-unwind" fn(*mut ()),
+        _context: *mut (),
+    ) -> *mut () {
+        panic!("objective-rust only supports macOS")
+    }
+
+    // Unwinds with the exception, so it needs the `"C-unwind"` ABI, unlike the rest of
+    // the runtime's functions.
+    #[cfg(target_os = "macos")]
+    #[link(name = "objc")]
+    extern "C-unwind" {
+        fn objc_exception_throw(exception: *mut ()) -> !;
+    }
+    #[cfg(not(target_os = "macos"))]
+    unsafe extern "C-unwind" fn objc_exception_throw(_exception: *mut ()) -> ! {
+        panic!("objective-rust only supports macOS")
+    }
+
+    // These don't call into the runtime, so they also run with the `stubs` feature.
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::foundation::{NSPoint, NSRect};
+
+        fn msg_send_ptr(imp: Implementation) -> *mut () {
+            imp.0.as_ptr()
+        }
+
+        #[test]
+        #[cfg(target_arch = "x86_64")]
+        fn large_structs_use_stret() {
+            assert_eq!(STRET_THRESHOLD, 16);
+            let stret = objc_msgSend_stret as *mut ();
+            let super_stret = objc_msgSendSuper_stret as *mut ();
+
+            assert_eq!(msg_send_ptr(get_msg_send_for::<NSRect>()), stret);
+            assert_eq!(
+                msg_send_ptr(get_msg_send_super_for::<NSRect>()),
+                super_stret
+            );
+            // Two registers' worth is still returned in registers.
+            assert_eq!(
+                msg_send_ptr(get_msg_send_for::<NSPoint>()),
+                objc_msgSend as *mut ()
+            );
+            assert_eq!(
+                msg_send_ptr(get_msg_send_super_for::<NSPoint>()),
+                objc_msgSendSuper as *mut ()
+            );
+        }
+
+        #[test]
+        #[cfg(not(target_arch = "x86_64"))]
+        fn every_return_uses_msg_send() {
+            assert_eq!(STRET_THRESHOLD, usize::MAX);
+            let msg_send = objc_msgSend as *mut ();
+            let msg_send_super = objc_msgSendSuper as *mut ();
+
+            assert_eq!(msg_send_ptr(get_msg_send_for::<NSRect>()), msg_send);
+            assert_eq!(msg_send_ptr(get_msg_send_for::<NSPoint>()), msg_send);
+            assert_eq!(
+                msg_send_ptr(get_msg_send_super_for::<NSRect>()),
+                msg_send_super
+            );
+        }
+
+        #[test]
+        fn small_returns_use_msg_send() {
+            let msg_send = objc_msgSend as *mut ();
+            assert_eq!(msg_send_ptr(get_msg_send_for::<()>()), msg_send);
+            assert_eq!(msg_send_ptr(get_msg_send_for::<f64>()), msg_send);
+            assert_eq!(msg_send_ptr(get_msg_send_for::<*mut ()>()), msg_send);
+            assert_eq!(msg_send_ptr(get_msg_send()), msg_send);
+        }
+
+        #[repr(transparent)]
+        struct Wrapper(NonNull<()>);
+        unsafe impl BoundClass for Wrapper {
+            type Instance = ();
+
+            fn get_objc_class() -> Class {
+                unreachable!()
+            }
+            unsafe fn from_raw(ptr: NonNull<()>) -> Self {
+                Self(ptr)
+            }
+        }
+
+        #[test]
+        // The borrows are what bindings write, to pick between the two traits.
+        #[allow(clippy::needless_borrow)]
+        fn borrowed_classes_are_passed_as_the_instance() {
+            use {BorrowedClass as _, BorrowedValue as _};
+
+            let mut instance = 0_u8;
+            let instance = NonNull::from(&mut instance).cast::<()>();
+            let mut wrapper = Wrapper(instance);
+            let arg = (&BorrowedArg(core::ptr::from_ref(&wrapper).cast_mut())).as_arg();
+            assert_eq!(arg, instance.as_ptr());
+            let arg = (&BorrowedArg(core::ptr::from_mut(&mut wrapper))).as_arg();
+            assert_eq!(arg, instance.as_ptr());
+
+            // Anything else is passed as a pointer to it.
+            let mut rect = NSRect::default();
+            let ptr = core::ptr::from_mut(&mut rect);
+            assert_eq!((&BorrowedArg(ptr)).as_arg(), ptr.cast());
+        }
+
+        #[test]
+        fn blocks_can_require_send_and_sync() {
+            use std::sync::atomic::{AtomicUsize, Ordering};
+
+            let total = Arc::new(AtomicUsize::new(0));
+            let block = Block::<dyn Fn(usize) + Send + Sync>::new({
+                let total = total.clone();
+                move |n| {
+                    total.fetch_add(n, Ordering::SeqCst);
+                }
+            });
+            (block.func)(2);
+            assert_eq!(total.load(Ordering::SeqCst), 2);
+            drop(block);
+            assert_eq!(Arc::strong_count(&total), 1);
+        }
+
+        #[test]
+        fn encodings_are_split_into_types() {
+            assert_eq!(split_encoding("v24@0:8@16"), ["v", "@", ":", "@"]);
+            assert_eq!(
+                split_encoding("{CGRect={CGPoint=dd}{CGSize=dd}}16@0:8"),
+                ["{CGRect={CGPoint=dd}{CGSize=dd}}", "@", ":"]
+            );
+            // Type qualifiers are dropped.
+            assert_eq!(
+                split_encoding("v32@0:8r^v16Q24"),
+                ["v", "@", ":", "^v", "Q"]
+            );
+        }
+
+        #[test]
+        fn encodings_match_by_kind_and_size() {
+            // Any pointer matches an object.
+            assert!(encodings_match("^v", "@"));
+            assert!(encodings_match("@", "#"));
+            assert!(encodings_match("q", "Q"));
+            assert!(encodings_match(
+                "{CGRect=}",
+                "{CGRect={CGPoint=dd}{CGSize=dd}}"
+            ));
+            assert!(encodings_match("?", "{CGRect={CGPoint=dd}{CGSize=dd}}"));
+
+            assert!(!encodings_match("i", "q"));
+            assert!(!encodings_match("f", "d"));
+            assert!(!encodings_match("q", "d"));
+            assert!(!encodings_match("v", "@"));
+            assert!(!encodings_match("{CGRect=}", "{CGSize=dd}"));
+        }
+
+        #[test]
+        fn rust_types_are_encoded() {
+            assert_eq!(<() as Encode>::ENCODING, "v");
+            assert_eq!(<NSInteger as Encode>::ENCODING, "q");
+            assert_eq!(<NSUInteger as Encode>::ENCODING, "Q");
+            assert_eq!(<*mut NSRect as Encode>::ENCODING, "^v");
+            assert_eq!(<Selector as Encode>::ENCODING, ":");
+            #[cfg(target_arch = "aarch64")]
+            assert_eq!(<bool as Encode>::ENCODING, "B");
+            #[cfg(not(target_arch = "aarch64"))]
+            assert_eq!(<bool as Encode>::ENCODING, "c");
+        }
     }
 }
@@ -0,0 +1,92 @@
+//! Timings for objective-rust's faster ways of calling methods. They only mean
+//! something in release builds, so they're ignored by default; run them with
+//! `cargo test --release --test benchmarks -- --ignored --nocapture`.
+#![cfg(target_os = "macos")]
+
+use {
+    objective_rust::{
+        ffi::{autoreleasepool, NSStr},
+        objrs,
+    },
+    std::{hint::black_box, time::Instant},
+};
+
+#[objrs]
+extern "objc" {
+    type NSProcessInfo;
+
+    #[singleton]
+    #[selector = "processInfo"]
+    fn process_info() -> Self;
+    #[selector = "processName"]
+    fn process_name(&self) -> NSStr;
+    #[cached]
+    #[selector = "processName"]
+    fn cached_process_name(&self) -> NSStr;
+}
+
+// The same class twice, with its vtable in each kind of storage.
+#[objrs]
+extern "objc" {
+    type ObjectiveRustGlobalVTable: NSObject;
+
+    #[nullable]
+    fn new() -> *mut Self;
+    fn hash(&self) -> usize;
+}
+
+#[objrs]
+extern "objc" {
+    #[vtable(thread_local)]
+    type ObjectiveRustThreadLocalVTable: NSObject;
+
+    #[nullable]
+    fn new() -> *mut Self;
+    fn hash(&self) -> usize;
+}
+
+const ITERATIONS: u32 = 1_000_000;
+
+/// Calls `func` [`ITERATIONS`] times, and prints how long each call took on average.
+fn time<R>(name: &str, mut func: impl FnMut() -> R) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(func());
+    }
+    println!("{name}: {:?}", start.elapsed() / ITERATIONS);
+}
+
+#[test]
+#[ignore]
+fn cached_strings() {
+    let info = NSProcessInfo::process_info();
+    // The first call fills the cache.
+    assert_eq!(info.cached_process_name(), info.process_name().as_str());
+
+    autoreleasepool(|| {
+        time("NSStr", || info.process_name().as_str().len());
+        time("NSStr copied into a String", || {
+            info.process_name().as_str().to_owned()
+        });
+        time("#[cached]", || info.cached_process_name().len());
+    });
+}
+
+#[test]
+#[ignore]
+fn vtable_storage() {
+    let global =
+        unsafe { ObjectiveRustGlobalVTable::from_raw(ObjectiveRustGlobalVTable::new().unwrap()) };
+    let thread_local = unsafe {
+        ObjectiveRustThreadLocalVTable::from_raw(ObjectiveRustThreadLocalVTable::new().unwrap())
+    };
+
+    // `preload` only gets the vtable, so this is the difference on its own.
+    time("global vtable access", ObjectiveRustGlobalVTable::preload);
+    time(
+        "thread-local vtable access",
+        ObjectiveRustThreadLocalVTable::preload,
+    );
+    time("global vtable call", || global.hash());
+    time("thread-local vtable call", || thread_local.hash());
+}
@@ -0,0 +1,104 @@
+//! Blocks are called with their arguments, and blocks stored by Objective-C objects
+//! outlive the `Block` they were made from.
+#![cfg(target_os = "macos")]
+
+use {
+    objective_rust::{
+        ffi::{self, autoreleasepool, Block},
+        objrs, ObjcBool,
+    },
+    std::{
+        cell::Cell,
+        rc::Rc,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    },
+};
+
+#[objrs]
+extern "objc" {
+    type NSBlockOperation;
+
+    #[constructor]
+    #[selector = "init"]
+    fn new() -> Option<Self>;
+    // Stores (copies) the block, and calls it when the operation is started. Execution
+    // blocks can run on other threads, at the same time as each other.
+    #[selector = "addExecutionBlock:"]
+    fn add_execution_block(&mut self, block: Block<dyn Fn() + Send + Sync>);
+    fn start(&self);
+}
+
+#[objrs]
+extern "objc" {
+    type NSArray;
+
+    #[nullable]
+    #[selector = "arrayWithObjects:count:"]
+    fn with_objects(objects: &[*mut ()]) -> *mut Self;
+    #[selector = "enumerateObjectsUsingBlock:"]
+    fn enumerate(&self, block: Block<dyn Fn(*mut (), usize, *mut ObjcBool) + '_>);
+}
+
+#[test]
+fn stored_block_outlives_block() {
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    autoreleasepool(|| {
+        let block_calls = calls.clone();
+        let block = Block::<dyn Fn() + Send + Sync>::new(move || {
+            block_calls.fetch_add(1, Ordering::SeqCst);
+        });
+        let mut operation = NSBlockOperation::new().unwrap();
+        operation.add_execution_block(&block);
+        drop(block);
+        // The operation's copy holds the only other reference to the closure.
+        assert_eq!(Arc::strong_count(&calls), 2);
+
+        operation.start();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    });
+
+    // Releasing the operation released its copy of the block, which dropped the
+    // closure.
+    assert_eq!(Arc::strong_count(&calls), 1);
+}
+
+#[test]
+fn heap_block_outlives_block() {
+    let calls = Rc::new(Cell::new(0));
+    let block_calls = calls.clone();
+    let block = Block::<dyn Fn()>::new(move || block_calls.set(block_calls.get() + 1));
+
+    let copy = block.copy();
+    let second_copy = copy.clone();
+    drop(block);
+    drop(copy);
+    assert_eq!(Rc::strong_count(&calls), 2);
+    drop(second_copy);
+    assert_eq!(Rc::strong_count(&calls), 1);
+}
+
+#[test]
+fn blocks_can_borrow_locals() {
+    let strings = [ffi::NSString::new("a"), ffi::NSString::new("b")];
+    let objects = strings
+        .iter()
+        .map(|string| string.as_ptr())
+        .collect::<Vec<_>>();
+    let seen = Cell::new(Vec::new());
+
+    autoreleasepool(|| {
+        let array = NSArray::with_objects(&objects).unwrap();
+        let array = unsafe { NSArray::from_raw(ffi::retain(array)) };
+        array.enumerate(&Block::new(|object, index, _stop| {
+            let mut list = seen.take();
+            list.push((object, index));
+            seen.set(list);
+        }));
+    });
+
+    assert_eq!(seen.take(), vec![(objects[0], 0), (objects[1], 1)]);
+}
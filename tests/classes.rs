@@ -0,0 +1,104 @@
+//! Classes defined from Rust: calls to the superclass, instance variables, and
+//! adopted protocols.
+#![cfg(target_os = "macos")]
+
+use {
+    objective_rust::{
+        ffi::{self, Instance},
+        objrs,
+    },
+    std::ffi::c_void,
+};
+
+#[objrs]
+extern "objc" {
+    type ObjectiveRustHasher: NSObject;
+
+    #[nullable]
+    fn new() -> *mut Self;
+
+    fn hash(&self) -> usize {
+        self.super_hash() + 1
+    }
+}
+
+#[objrs]
+extern "objc" {
+    #[ivar(_state: *mut c_void)]
+    type ObjectiveRustState: NSObject;
+
+    #[nullable]
+    fn new() -> *mut Self;
+}
+
+#[objrs]
+extern "objc" {
+    #[protocol]
+    type NSCopying;
+
+    #[selector = "copyWithZone:"]
+    fn copy_with_zone(&self, zone: *mut ()) -> *mut ();
+
+    #[protocols(NSCopying)]
+    type ObjectiveRustCopyable: NSObject;
+
+    #[nullable]
+    fn new() -> *mut Self;
+}
+
+impl NSCopyingMethods for ObjectiveRustCopyable {
+    fn copy_with_zone(&self, _zone: *mut ()) -> *mut () {
+        // Immutable objects can return themselves from `copy`.
+        unsafe { ffi::retain(self.into_raw()) }.as_ptr().cast()
+    }
+}
+
+#[test]
+fn overrides_can_call_the_superclass() {
+    let object = unsafe { ObjectiveRustHasher::from_raw(ObjectiveRustHasher::new().unwrap()) };
+    let hash: usize = unsafe {
+        ffi::msg_send(
+            Instance::from_ptr(object.into_raw()),
+            ffi::get_selector("hash").unwrap(),
+            (),
+        )
+    };
+    assert_eq!(hash, object.super_hash() + 1);
+    assert_eq!(object.hash(), hash);
+}
+
+#[test]
+fn ivars_store_values() {
+    let new = || unsafe { ObjectiveRustState::from_raw(ObjectiveRustState::new().unwrap()) };
+    let mut object = new();
+    assert!(object.state().is_null());
+
+    let mut value = 5;
+    let ptr: *mut c_void = (&mut value as *mut i32).cast();
+    object.set_state(ptr);
+    assert_eq!(object.state(), ptr);
+
+    // Each object has its own ivars.
+    assert!(new().state().is_null());
+}
+
+#[test]
+fn adopted_protocols_are_registered() {
+    let protocol = ffi::get_protocol("NSCopying").unwrap();
+    assert!(ffi::class_conforms_to(
+        ObjectiveRustCopyable::get_objc_class(),
+        protocol
+    ));
+
+    let object = unsafe { ObjectiveRustCopyable::from_raw(ObjectiveRustCopyable::new().unwrap()) };
+    let copy: *mut () = unsafe {
+        ffi::msg_send(
+            Instance::from_ptr(object.into_raw()),
+            ffi::get_selector("copy").unwrap(),
+            (),
+        )
+    };
+    assert_eq!(copy, object.into_raw().as_ptr().cast());
+    // Take back the reference `copy` returned.
+    drop(unsafe { ObjectiveRustCopyable::from_raw(object.into_raw()) });
+}
@@ -0,0 +1,277 @@
+//! Calling bound methods: return types, argument conventions, and the attributes that
+//! change how a method is called.
+#![cfg(target_os = "macos")]
+
+use objective_rust::{
+    ffi::{self, autoreleasepool, Instance, LoadError, NSStr},
+    foundation::{NSPoint, NSRect, NSSize},
+    objrs,
+};
+
+#[objrs]
+extern "objc" {
+    type NSMutableArray;
+
+    #[constructor]
+    #[selector = "init"]
+    fn new() -> Option<Self>;
+    #[selector = "addObject:"]
+    fn add_object(&mut self, object: *mut ());
+    #[selector = "removeAllObjects"]
+    fn remove_all_objects(&mut self);
+    fn count(&self) -> usize;
+    fn description(&self) -> NSStr;
+    // An array's description isn't immutable, which shows the first one is kept.
+    #[cached]
+    #[selector = "description"]
+    fn cached_description(&self) -> NSStr;
+}
+
+#[objrs]
+extern "objc" {
+    type NSArray;
+
+    #[nullable]
+    fn alloc() -> *mut Self;
+    #[variadic]
+    #[selector = "initWithObjects:"]
+    fn init_with_objects(self, objects: &[*mut ()]) -> Self;
+    fn count(&self) -> usize;
+    #[selector = "objectAtIndex:"]
+    fn object_at_index(&self, index: usize) -> *mut ();
+}
+
+#[objrs]
+extern "objc" {
+    type NSString;
+
+    #[bool_is_success]
+    #[selector = "getCString:maxLength:encoding:"]
+    fn get_c_string(&self, buffer: *mut u8, max_length: usize, encoding: usize) -> bool;
+}
+
+#[objrs]
+extern "objc" {
+    type NSValue;
+
+    #[nullable]
+    #[selector = "valueWithRect:"]
+    fn value_with_rect(rect: NSRect) -> *mut Self;
+    #[selector = "rectValue"]
+    fn rect_value(&self) -> NSRect;
+    #[out_struct]
+    #[selector = "getValue:"]
+    fn get_rect(&self) -> NSRect;
+}
+
+#[objrs(framework = "AppKit")]
+extern "objc" {
+    type NSColor;
+
+    #[nullable]
+    #[selector = "colorWithSRGBRed:green:blue:alpha:"]
+    fn with_srgb(red: f64, green: f64, blue: f64, alpha: f64) -> *mut Self;
+    #[out_params]
+    #[selector = "getRed:green:blue:alpha:"]
+    fn rgba(&self) -> (f64, f64, f64, f64);
+}
+
+#[objrs]
+extern "objc" {
+    type ObjectiveRustSwizzled: NSObject;
+
+    #[nullable]
+    fn new() -> *mut Self;
+
+    fn first(&self) -> u32 {
+        1
+    }
+    fn second(&self) -> u32 {
+        2
+    }
+}
+
+#[objrs]
+extern "objc" {
+    type ObjectiveRustMissingClass;
+
+    fn count(&self) -> usize;
+}
+
+#[objrs]
+extern "objc" {
+    type NSDictionary;
+
+    #[selector = "objectForMissingKey:"]
+    fn object_for_missing_key(&self, key: *mut ()) -> *mut ();
+}
+
+#[objrs]
+extern "objc" {
+    type NSSet;
+
+    // `count` returns an `NSUInteger`, not a float.
+    fn count(&self) -> f64;
+}
+
+const NS_UTF8_STRING_ENCODING: usize = 4;
+
+#[test]
+fn void_methods() {
+    let mut array = NSMutableArray::new().unwrap();
+    let object = ffi::NSString::new("hello");
+    array.add_object(object.as_ptr());
+    array.add_object(object.as_ptr());
+    assert_eq!(array.count(), 2);
+
+    array.remove_all_objects();
+    assert_eq!(array.count(), 0);
+}
+
+#[test]
+fn classes_can_be_loaded_up_front() {
+    NSMutableArray::preload();
+    // Loading it again does nothing.
+    NSMutableArray::preload();
+    assert_eq!(NSMutableArray::new().unwrap().count(), 0);
+}
+
+#[test]
+fn try_load_reports_missing_bindings() {
+    assert!(NSMutableArray::try_load().is_ok());
+    assert_eq!(NSMutableArray::new().unwrap().count(), 0);
+
+    assert!(matches!(
+        ObjectiveRustMissingClass::try_load(),
+        Err(LoadError::Class("ObjectiveRustMissingClass"))
+    ));
+    assert!(matches!(
+        NSDictionary::try_load(),
+        Err(LoadError::Selector {
+            class: "NSDictionary",
+            selector: "objectForMissingKey:",
+        })
+    ));
+    // Loading the vtable would panic in debug builds, so the mismatch is returned
+    // instead.
+    #[cfg(debug_assertions)]
+    assert!(matches!(
+        NSSet::try_load(),
+        Err(LoadError::Encoding {
+            class: "NSSet",
+            selector: "count",
+        })
+    ));
+}
+
+#[test]
+fn bool_is_success() {
+    let string = ffi::NSString::new("hello");
+    let string = unsafe { NSString::from_raw(string.into_raw()) };
+
+    let mut buffer = [0u8; 16];
+    let result = string.get_c_string(buffer.as_mut_ptr(), buffer.len(), NS_UTF8_STRING_ENCODING);
+    assert_eq!(result, Ok(()));
+    assert_eq!(&buffer[..6], b"hello\0");
+
+    // Too small for the string and its nul terminator.
+    let result = string.get_c_string(buffer.as_mut_ptr(), 3, NS_UTF8_STRING_ENCODING);
+    assert_eq!(result, Err(()));
+}
+
+#[test]
+fn large_structs_are_returned() {
+    let rect = NSRect {
+        origin: NSPoint { x: 1.0, y: 2.0 },
+        size: NSSize {
+            width: 3.0,
+            height: 4.0,
+        },
+    };
+
+    autoreleasepool(|| {
+        let value = NSValue::value_with_rect(rect).unwrap();
+        let value = unsafe { NSValue::from_raw(ffi::retain(value)) };
+        assert_eq!(value.rect_value(), rect);
+        assert_eq!(value.get_rect(), rect);
+    });
+}
+
+#[test]
+fn variadic_lists_of_any_length() {
+    let strings = [
+        ffi::NSString::new("a"),
+        ffi::NSString::new("b"),
+        ffi::NSString::new("c"),
+    ];
+    let objects = strings
+        .iter()
+        .map(|string| string.as_ptr())
+        .collect::<Vec<_>>();
+
+    for len in [0, 1, 3] {
+        let array = unsafe { NSArray::from_raw(NSArray::alloc().unwrap()) };
+        let array = array.init_with_objects(&objects[..len]);
+        assert_eq!(array.count(), len);
+        for (index, object) in objects[..len].iter().enumerate() {
+            assert_eq!(array.object_at_index(index), *object);
+        }
+    }
+}
+
+#[test]
+fn swizzled_methods_are_swapped() {
+    let object = unsafe { ObjectiveRustSwizzled::from_raw(ObjectiveRustSwizzled::new().unwrap()) };
+    let send = |selector: &str| -> u32 {
+        let selector = ffi::get_selector(selector).unwrap();
+        unsafe { ffi::msg_send(Instance::from_ptr(object.into_raw()), selector, ()) }
+    };
+    assert_eq!(send("first"), 1);
+
+    assert!(unsafe { ObjectiveRustSwizzled::swizzle("first", "second") });
+    assert_eq!(send("first"), 2);
+    assert_eq!(send("second"), 1);
+    // Calls from Rust go straight to the body.
+    assert_eq!(object.first(), 1);
+
+    assert!(unsafe { ObjectiveRustSwizzled::swizzle("first", "second") });
+    assert_eq!(send("first"), 1);
+
+    assert!(!unsafe { ObjectiveRustSwizzled::swizzle("first", "missing") });
+    assert_eq!(send("first"), 1);
+}
+
+#[test]
+fn out_params_are_returned_as_a_tuple() {
+    autoreleasepool(|| {
+        let color = NSColor::with_srgb(0.25, 0.5, 0.75, 1.0).unwrap();
+        let color = unsafe { NSColor::from_raw(ffi::retain(color)) };
+        let (red, green, blue, alpha) = color.rgba();
+        for (value, expected) in [(red, 0.25), (green, 0.5), (blue, 0.75), (alpha, 1.0)] {
+            assert!((value - expected).abs() < 1e-6, "{value} != {expected}");
+        }
+    });
+}
+
+#[test]
+fn cached_strings_are_copied_once() {
+    let mut array = NSMutableArray::new().unwrap();
+    let (first, ptr) = autoreleasepool(|| {
+        let cached = array.cached_description();
+        assert_eq!(cached, array.description().as_str());
+        (cached.to_owned(), cached.as_ptr())
+    });
+
+    array.add_object(ffi::NSString::new("hello").as_ptr());
+    autoreleasepool(|| {
+        assert_ne!(array.description().as_str(), first);
+        // The same copy is returned every time.
+        let cached = array.cached_description();
+        assert_eq!(cached, first);
+        assert_eq!(cached.as_ptr(), ptr);
+    });
+
+    // Each instance has its own cache.
+    let other = NSMutableArray::new().unwrap();
+    assert_ne!(other.cached_description().as_ptr(), ptr);
+}
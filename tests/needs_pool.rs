@@ -0,0 +1,42 @@
+//! `#[needs_pool]` methods check that an `AutoreleasePool` is alive in debug builds.
+#![cfg(target_os = "macos")]
+
+use objective_rust::{
+    ffi::{autorelease_pool_active, AutoreleasePool},
+    objrs,
+};
+
+#[objrs]
+extern "objc" {
+    type NSString;
+
+    // Returns an autoreleased, empty string.
+    #[needs_pool]
+    #[nullable]
+    fn string() -> *mut Self;
+}
+
+#[test]
+fn pools_are_counted() {
+    assert!(!autorelease_pool_active());
+    let outer = AutoreleasePool::new();
+    let inner = AutoreleasePool::new();
+    assert!(autorelease_pool_active());
+    drop(inner);
+    assert!(autorelease_pool_active());
+    drop(outer);
+    assert!(!autorelease_pool_active());
+}
+
+#[test]
+fn call_inside_pool() {
+    let _pool = AutoreleasePool::new();
+    assert!(NSString::string().is_some());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "has to be called inside an `AutoreleasePool`")]
+fn call_without_pool() {
+    NSString::string();
+}
@@ -0,0 +1,241 @@
+//! Ownership of bound objects: retains and releases, ownership transfers, and weak
+//! references.
+#![cfg(target_os = "macos")]
+
+use {
+    objective_rust::{
+        ffi::{self, autoreleasepool, AutoreleasePool, Class, Id, WeakId},
+        objrs,
+    },
+    std::ptr::NonNull,
+};
+
+#[objrs]
+extern "objc" {
+    type NSObject;
+
+    #[nullable]
+    fn alloc() -> *mut Self;
+    #[constructor]
+    #[selector = "init"]
+    fn new() -> Option<Self>;
+    // `self` returns the instance it's called on, so it can be chained.
+    #[selector = "self"]
+    fn this(self) -> Self;
+    fn autorelease(self) -> *mut Self;
+    #[selector = "isKindOfClass:"]
+    fn is_kind_of_class(&self, class: Class) -> bool;
+}
+
+#[objrs]
+extern "objc" {
+    type NSAutoreleasePool;
+
+    // Adds the object to the current pool, which releases it when it's drained.
+    #[selector = "addObject:"]
+    fn add_object(#[consumed] object: NSObject);
+}
+
+#[objrs]
+extern "objc" {
+    type NSMutableString;
+
+    #[constructor]
+    #[selector = "init"]
+    fn new() -> Option<Self>;
+    #[selector = "appendString:"]
+    fn append_string(&self, string: *mut ());
+    fn length(&self) -> usize;
+}
+
+#[objrs]
+extern "objc" {
+    type NSTask;
+
+    #[constructor]
+    #[selector = "init"]
+    fn new() -> Option<Self>;
+    // Only the setter is called: the getter doesn't return an owned reference.
+    #[property]
+    #[selector = "standardOutput"]
+    fn standard_output(&self) -> NSObject;
+}
+
+#[objrs]
+extern "objc" {
+    type NSNumber;
+
+    #[constructor]
+    #[selector = "initWithLongLong:"]
+    fn with_i64(value: i64) -> Option<Self>;
+    #[selector = "longLongValue"]
+    fn i64_value(&self) -> i64;
+}
+
+#[objrs]
+extern "objc" {
+    type NSData;
+
+    #[nullable]
+    fn alloc() -> *mut Self;
+    #[selector = "initWithContentsOfFile:"]
+    fn init_with_contents_of_file(self, path: *mut ()) -> Option<Self>;
+    fn length(&self) -> usize;
+}
+
+#[test]
+fn class_arguments() {
+    let object = NSObject::new().unwrap();
+    assert!(object.is_kind_of_class(NSObject::get_objc_class()));
+    assert!(!object.is_kind_of_class(NSMutableString::get_objc_class()));
+
+    let string = NSMutableString::new().unwrap();
+    assert!(string.is_kind_of(NSObject::get_objc_class()));
+}
+
+#[test]
+fn consumed_arguments_are_not_released_again() {
+    let object = Id::from(NSObject::new().unwrap());
+    let weak = WeakId::new(&object);
+
+    autoreleasepool(|| {
+        NSAutoreleasePool::add_object(NSObject::from(object));
+        // The pool holds the only reference now.
+        assert!(weak.upgrade().is_some());
+    });
+
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn autoreleased_objects_are_freed_with_the_pool() {
+    let object = Id::from(NSObject::new().unwrap());
+    let weak = WeakId::new(&object);
+
+    autoreleasepool(|| {
+        NSObject::from(object).autorelease();
+        assert!(weak.upgrade().is_some());
+    });
+
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn pool_guards_drain_when_dropped() {
+    assert!(!ffi::autorelease_pool_active());
+    let object = Id::from(NSObject::new().unwrap());
+    let weak = WeakId::new(&object);
+
+    let outer = AutoreleasePool::new();
+    let inner = AutoreleasePool::new();
+    assert!(ffi::autorelease_pool_active());
+    NSObject::from(object).autorelease();
+    drop(inner);
+    assert!(weak.upgrade().is_none());
+    assert!(ffi::autorelease_pool_active());
+    drop(outer);
+    assert!(!ffi::autorelease_pool_active());
+}
+
+#[test]
+fn weak_references_do_not_keep_objects_alive() {
+    let object = Id::from(NSObject::new().unwrap());
+    let weak = WeakId::new(&object);
+    let strong = weak.upgrade().unwrap();
+    assert_eq!(strong.as_ptr(), object.as_ptr());
+
+    drop(strong);
+    assert!(weak.upgrade().is_some());
+    drop(object);
+    assert!(weak.upgrade().is_none());
+    assert!(weak.clone().upgrade().is_none());
+}
+
+#[test]
+fn tagged_pointers_can_be_dropped() {
+    // Small numbers are usually tagged pointers, which aren't real objects; large
+    // ones can't be.
+    for value in [5, i64::MAX] {
+        let number = NSNumber::with_i64(value).unwrap();
+        let clone = number.clone();
+        assert_eq!(clone.i64_value(), value);
+        drop(number);
+        assert_eq!(clone.i64_value(), value);
+    }
+}
+
+#[test]
+fn allocated_objects_can_be_dropped_without_init() {
+    let object = unsafe { NSObject::from_raw(NSObject::alloc().unwrap()) };
+    drop(object);
+}
+
+#[test]
+fn failed_initialisers_return_none() {
+    let path = ffi::NSString::new("/objective-rust/does/not/exist");
+    let data = unsafe { NSData::from_raw(NSData::alloc().unwrap()) };
+    assert!(data.init_with_contents_of_file(path.as_ptr()).is_none());
+
+    // Every macOS install has this file.
+    let path = ffi::NSString::new("/System/Library/CoreServices/SystemVersion.plist");
+    let data = unsafe { NSData::from_raw(NSData::alloc().unwrap()) };
+    let data = data.init_with_contents_of_file(path.as_ptr()).unwrap();
+    assert!(data.length() > 0);
+}
+
+#[test]
+fn chained_methods_keep_the_same_reference() {
+    let object = NSObject::new().unwrap();
+    let ptr = object.into_raw();
+    #[cfg(debug_assertions)]
+    let retain_count = unsafe { ffi::retain_count(ptr) };
+
+    let object = object.this().this().this();
+    assert_eq!(object.into_raw(), ptr);
+    #[cfg(debug_assertions)]
+    assert_eq!(unsafe { ffi::retain_count(ptr) }, retain_count);
+}
+
+#[test]
+fn clones_share_the_object() {
+    let string = NSMutableString::new().unwrap();
+    let clone = string.clone();
+    assert_eq!(string.into_raw(), clone.into_raw());
+
+    clone.append_string(ffi::NSString::new("hello").as_ptr());
+    assert_eq!(string.length(), 5);
+    drop(clone);
+    assert_eq!(string.length(), 5);
+}
+
+#[test]
+fn instance_pointer_round_trip() {
+    let object = NSObject::new().unwrap();
+    let ptr = object.into_raw();
+    #[cfg(debug_assertions)]
+    let retain_count = unsafe { ffi::retain_count(ptr) };
+
+    // Converting into the pointer gives up the wrapper's reference, and `from_raw`
+    // takes it back.
+    let raw: NonNull<NSObjectInstance> = object.into();
+    assert_eq!(raw, ptr);
+    let object = unsafe { NSObject::from_raw(raw) };
+    assert_eq!(object.into_raw(), ptr);
+    #[cfg(debug_assertions)]
+    assert_eq!(unsafe { ffi::retain_count(ptr) }, retain_count);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn property_setters_borrow_objects() {
+    let mut task = NSTask::new().unwrap();
+    let object = NSObject::new().unwrap();
+    let ptr = object.into_raw();
+    let retain_count = unsafe { ffi::retain_count(ptr) };
+
+    // The task keeps its own reference, and the caller keeps theirs.
+    task.set_standard_output(&object);
+    assert_eq!(unsafe { ffi::retain_count(ptr) }, retain_count + 1);
+    drop(task);
+    assert_eq!(unsafe { ffi::retain_count(ptr) }, retain_count);
+}